@@ -1,17 +1,40 @@
 //! Draw the basemap
 
 use std::path::PathBuf;
-use shapefile::Reader;
-use svg::{Document, node::element};
+use svg::Node;
 
+pub mod attributes;
+#[cfg(feature = "csv")]
+pub mod csv_points;
+pub mod defs;
 pub mod draw_svg;
+pub mod effects;
+pub mod frame;
+#[cfg(feature = "geopackage")]
+pub mod geopackage;
+#[cfg(feature = "gpx")]
+pub mod gpx;
+pub mod graticule;
+pub mod labels;
+pub mod layout;
+pub mod legend;
+pub mod north_arrow;
+pub mod overlay;
+pub mod polylabel;
+pub mod scale_bar;
 pub mod shapefile_visualizer;
+pub mod sources;
 pub mod styles;
+pub mod title;
 pub mod utils;
 
-use styles::{Style, LayerStyle};
-use draw_svg::{set_background, draw_polyline};
-use utils::mapping_function;
+use defs::DefinedIds;
+use draw_svg::set_background;
+use graticule::{draw_equator, draw_graticules, draw_reference_lines};
+use labels::LabelPlacer;
+use layout::{Layout, draw_layout_reporting};
+use overlay::{Overlay, layers_below};
+use styles::Style;
 
 /// Map struct
 /// This struct contains the information needed to draw a map
@@ -30,6 +53,13 @@ pub struct Map {
     pub lon_max: f64,
 }
 
+/// The features of a layer that were skipped for lack of a drawable geometry
+pub struct SkippedFeatures<'a> {
+    /// The file name of the layer, as given in its style
+    pub filename: &'a str,
+    /// The number of features skipped
+    pub count: usize,
+}
 
 /// Draws the complete basemap using the ocean style
 ///
@@ -37,88 +67,206 @@ pub struct Map {
 /// 1. Sets the background using the ocean layer
 /// 2. Loads and draws each layer in sequence
 /// 3. Applies the appropriate styles to each feature
+/// 4. Places the labels of all layers without overlap
 ///
 /// # Arguments
 /// * `map` - The map dimensions and projection settings
-/// * `document` - The SVG document to modify
+/// * `document` - The SVG document or group to modify
 /// * `style` - The style to use for the basemap
-pub fn draw_basemap(map: &Map, document: &mut Document, style: &Style) {
-    set_background(map, document, &style.background);
-    for layer in &style.layers {
-        let file_path = PathBuf::from(format!("data/10m_physical/{}", layer.filename));
-        let reader = Reader::from_path(&file_path)
-            .unwrap_or_else(|_| panic!("Error loading shapefile: {}", file_path.display()));
-        shapefile_visualizer::visualize_shapefile(map, reader, document, &layer.layer_style);
-    }
-    // Draw graticules and equator
-    draw_graticules(map, document, &style.graticule_style);
-    draw_equator(map, document, &style.equator_style);
-    // Todo draw user defined content
-    //draw_text((500.0, 500.0), "Hello, world!", document, 12, "black");
+///
+/// # Panics
+/// Panics if a data file of the style cannot be loaded.
+pub fn draw_basemap(map: &Map, document: &mut impl Node, style: &Style) {
+    draw_basemap_reporting(map, document, style);
 }
 
-/// Draw a map
-pub fn draw_map(map: &Map,style: &Style, output_path: &PathBuf) {
-    let mut document = svg::Document::new().set("viewBox", (0, 0, map.cols, map.rows));
-    
-    draw_basemap(map, &mut document, style);
-
-    svg::save(output_path, &document).expect("Error saving svg");
-    utils::svg_to_png(output_path, &PathBuf::from("Map.png"));
+/// Draws the complete basemap like [`draw_basemap`] and reports the features it skipped
+///
+/// Returns the layers with skipped features, see [`SkippedFeatures`].
+///
+/// # Panics
+/// Panics if a data file of the style cannot be loaded.
+pub fn draw_basemap_reporting<'a>(
+    map: &Map,
+    document: &mut impl Node,
+    style: &Style<'a>,
+) -> Vec<SkippedFeatures<'a>> {
+    draw_basemap_with_overlays_reporting(map, document, style, &[])
 }
 
-/// Draws the graticule grid (latitude and longitude lines)
+/// Draws the complete basemap with user overlays between or above its layers
 ///
-/// This function draws a grid of lines at 15-degree intervals for both longitude and latitude.
-/// The grid helps users understand the geographic coordinates on the map.
+/// Each overlay is drawn at its z-order, overlays of the same z-order in the given order.
+/// The labels of the overlays are placed together with those of the layers.
 ///
 /// # Arguments
 /// * `map` - The map dimensions and projection settings
-/// * `document` - The SVG document to modify
-/// * `graticule_style` - The style to use for the graticule lines
-///
-/// # Example
-/// ```
-/// let map = Map { /* ... */ };
-/// let mut document = Document::new();
-/// let style = Style { /* ... */ };
-/// draw_graticules(&map, &mut document, &style.graticule_style);
-/// ```
-fn draw_graticules(map: &Map, document: &mut Document, graticule_style: &LayerStyle) {
-    let data = element::path::Data::new();
+/// * `document` - The SVG document or group to modify
+/// * `style` - The style to use for the basemap
+/// * `overlays` - The user geometries to draw with the basemap
+///
+/// # Panics
+/// Panics if a data file of the style cannot be loaded.
+pub fn draw_basemap_with_overlays<'a>(
+    map: &Map,
+    document: &mut impl Node,
+    style: &Style<'a>,
+    overlays: &[Overlay<'a>],
+) {
+    draw_basemap_with_overlays_reporting(map, document, style, overlays);
+}
 
-    // Draw meridians (vertical lines)
-    for lon in (-180..=180).step_by(15) {
-        let mut points = Vec::new();
-        for lat in (map.lat_min.floor() as i32..=map.lat_max.ceil() as i32).step_by(1) {
-            points.push(mapping_function(lon as f64, lat as f64, map));
+/// Draws the basemap with overlays like [`draw_basemap_with_overlays`]
+/// and reports the features it skipped
+///
+/// Returns the layers with skipped features, see [`SkippedFeatures`].
+///
+/// # Panics
+/// Panics if a data file of the style cannot be loaded.
+pub fn draw_basemap_with_overlays_reporting<'a>(
+    map: &Map,
+    document: &mut impl Node,
+    style: &Style<'a>,
+    overlays: &[Overlay<'a>],
+) -> Vec<SkippedFeatures<'a>> {
+    // Layers sharing a pattern, gradient or filter share its definition
+    let mut defined = DefinedIds::default();
+    set_background(map, document, &style.background, &mut defined);
+    let mut labels = LabelPlacer::new();
+    let layer_count = style.layers.len();
+    let mut skipped = Vec::new();
+    draw_overlays(
+        map,
+        document,
+        overlays,
+        Some(0),
+        layer_count,
+        &mut labels,
+        &mut defined,
+    );
+    for (index, layer) in style.layers.iter().enumerate() {
+        let file_path = PathBuf::from("data/10m_physical").join(layer.filename);
+        let count = sources::visit_features(&file_path, |features| {
+            shapefile_visualizer::visualize_features(
+                map,
+                features,
+                document,
+                &layer.layer_style,
+                &mut labels,
+                &mut defined,
+            )
+        });
+        if count > 0 {
+            skipped.push(SkippedFeatures {
+                filename: layer.filename,
+                count,
+            });
         }
-        draw_polyline(&points, document, graticule_style, data.clone());
+        draw_overlays(
+            map,
+            document,
+            overlays,
+            Some(index + 1),
+            layer_count,
+            &mut labels,
+            &mut defined,
+        );
     }
+    // Draw graticules, equator and the other special lines
+    draw_graticules(map, document, &style.graticule, &style.graticule_style);
+    draw_equator(map, document, &style.equator_style);
+    draw_reference_lines(map, document, &style.reference_lines);
+    draw_overlays(
+        map,
+        document,
+        overlays,
+        None,
+        layer_count,
+        &mut labels,
+        &mut defined,
+    );
+    // Labels go on top of everything else
+    labels.draw(document);
+    skipped
+}
 
-    // Draw parallels (horizontal lines) 
-    for lat in (-90..=90).step_by(15) {
-        let mut points = Vec::new();
-        for lon in (map.lon_min.floor() as i32..=map.lon_max.ceil() as i32).step_by(1) {
-            points.push(mapping_function(lon as f64, lat as f64, map));
+/// Draws the overlays with the given number of layers beneath them
+fn draw_overlays<'a>(
+    map: &Map,
+    document: &mut impl Node,
+    overlays: &[Overlay<'a>],
+    below: Option<usize>,
+    layer_count: usize,
+    labels: &mut LabelPlacer<'a>,
+    defined: &mut DefinedIds,
+) {
+    for overlay in overlays {
+        if layers_below(overlay.z_order, layer_count) == below {
+            // The shapes of an overlay are all drawable, so none are skipped
+            shapefile_visualizer::visualize_features(
+                map,
+                overlay.shapes(),
+                document,
+                &overlay.layer_style,
+                labels,
+                defined,
+            );
         }
-        draw_polyline(&points, document, graticule_style, data.clone());
     }
 }
 
-/// Draws the equator line
+/// Draw a map
 ///
-/// # Arguments
-/// * `map` - The map dimensions and projection settings
-/// * `document` - The SVG document to modify
-/// * `equator_style` - The style to use for the equator line
-fn draw_equator(map: &Map, document: &mut Document, equator_style: &LayerStyle) {
-    let data = element::path::Data::new();
-    let mut points = Vec::new();
-    
-    // Draw equator (0° latitude)
-    for lon in (map.lon_min.floor() as i32..=map.lon_max.ceil() as i32).step_by(1) {
-        points.push(mapping_function(lon as f64, 0.0, map));
-    }
-    draw_polyline(&points, document, equator_style, data);
+/// # Panics
+/// Panics if a shapefile cannot be loaded or the output cannot be written.
+pub fn draw_map(map: &Map, style: &Style, output_path: &PathBuf) {
+    draw_map_with_layout(map, style, &Layout::default(), output_path);
+}
+
+/// Draw a map like [`draw_map`] and report the features it skipped
+///
+/// Returns the layers with skipped features, see [`SkippedFeatures`].
+///
+/// # Panics
+/// Panics if a shapefile cannot be loaded or the output cannot be written.
+#[must_use]
+pub fn draw_map_reporting<'a>(
+    map: &Map,
+    style: &Style<'a>,
+    output_path: &PathBuf,
+) -> Vec<SkippedFeatures<'a>> {
+    draw_map_with_layout_reporting(map, style, &Layout::default(), output_path)
+}
+
+/// Draw a map with decorations like a frame, legend or title around it
+///
+/// # Panics
+/// Panics if a shapefile cannot be loaded or the output cannot be written.
+pub fn draw_map_with_layout<'a>(
+    map: &Map,
+    style: &Style<'a>,
+    layout: &Layout<'a>,
+    output_path: &PathBuf,
+) {
+    let _ = draw_map_with_layout_reporting(map, style, layout, output_path);
+}
+
+/// Draw a map with decorations like [`draw_map_with_layout`] and report the features it skipped
+///
+/// Returns the layers with skipped features, see [`SkippedFeatures`].
+///
+/// # Panics
+/// Panics if a shapefile cannot be loaded or the output cannot be written.
+#[must_use]
+pub fn draw_map_with_layout_reporting<'a>(
+    map: &Map,
+    style: &Style<'a>,
+    layout: &Layout<'a>,
+    output_path: &PathBuf,
+) -> Vec<SkippedFeatures<'a>> {
+    let (document, skipped) = draw_layout_reporting(map, style, layout);
+
+    svg::save(output_path, &document).expect("Error saving svg");
+    utils::svg_to_png(output_path, &PathBuf::from("Map.png"));
+    skipped
 }
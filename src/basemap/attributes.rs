@@ -0,0 +1,74 @@
+//! Reading attribute values from the records of features
+
+use shapefile::dbase::{FieldValue, Record};
+
+use super::styles::FeatureFilter;
+
+/// Returns the numeric value of a field, `None` if the field is missing, empty or not numeric
+#[must_use]
+pub fn number(record: &Record, field: &str) -> Option<f64> {
+    match get(record, field)? {
+        FieldValue::Numeric(value) => *value,
+        FieldValue::Float(value) => value.map(f64::from),
+        FieldValue::Double(value) | FieldValue::Currency(value) => Some(*value),
+        FieldValue::Integer(value) => Some(f64::from(*value)),
+        FieldValue::Character(Some(value)) => value.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Returns the text value of a field, `None` if the field is missing, empty or not text
+#[must_use]
+pub fn text<'r>(record: &'r Record, field: &str) -> Option<&'r str> {
+    match get(record, field)? {
+        FieldValue::Character(Some(value)) | FieldValue::Memo(value) => {
+            Some(value.trim()).filter(|value| !value.is_empty())
+        }
+        _ => None,
+    }
+}
+
+/// Returns the text of the first of the fields that has one
+#[must_use]
+pub fn first_text<'r, 'f>(
+    record: &'r Record,
+    fields: impl IntoIterator<Item = &'f str>,
+) -> Option<&'r str> {
+    fields.into_iter().find_map(|field| text(record, field))
+}
+
+/// Returns whether the attributes of a feature meet the condition of a filter
+#[must_use]
+pub fn matches(record: &Record, filter: &FeatureFilter) -> bool {
+    match *filter {
+        FeatureFilter::OneOf { field, values } => {
+            if let Some(value) = text(record, field) {
+                values
+                    .iter()
+                    .any(|candidate| candidate.eq_ignore_ascii_case(value))
+            } else if let Some(value) = number(record, field) {
+                values
+                    .iter()
+                    .any(|candidate| candidate.trim().parse::<f64>() == Ok(value))
+            } else {
+                false
+            }
+        }
+        FeatureFilter::Between { field, min, max } => {
+            number(record, field).is_some_and(|value| (min..=max).contains(&value))
+        }
+    }
+}
+
+/// Returns the value of a field, matching the field name case-insensitively if there is no exact match
+///
+/// Natural Earth uses both `name` and `NAME` style field names across its datasets.
+fn get<'r>(record: &'r Record, field: &str) -> Option<&'r FieldValue> {
+    record.get(field).or_else(|| {
+        record
+            .as_ref()
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(field))
+            .map(|(_, value)| value)
+    })
+}
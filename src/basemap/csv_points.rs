@@ -0,0 +1,195 @@
+//! This module provides functionality for reading points from CSV files with coordinate columns.
+
+use std::path::Path;
+
+use geo::{Geometry, Point};
+use shapefile::dbase::{FieldValue, Record};
+
+/// The columns of a CSV file holding the position and label of each point
+pub struct CsvColumns<'a> {
+    /// The header of the latitude column, in decimal degrees
+    pub lat: &'a str,
+    /// The header of the longitude column, in decimal degrees
+    pub lon: &'a str,
+    /// The header of the column labeling the points, `None` to keep the label field of the layer style
+    pub label: Option<&'a str>,
+    /// The character separating the columns
+    /// (e.g. `b','`, `b';'`, `b'\t'`)
+    pub delimiter: u8,
+}
+
+impl Default for CsvColumns<'_> {
+    /// Comma separated "latitude" and "longitude" columns, labeled by the "name" column
+    fn default() -> Self {
+        CsvColumns {
+            lat: "latitude",
+            lon: "longitude",
+            label: Some("name"),
+            delimiter: b',',
+        }
+    }
+}
+
+/// Reads the rows of a CSV file with a header as points with all columns as attributes
+///
+/// Headers are matched ignoring case. Values that are finite numbers become numeric fields,
+/// so they can drive the marker size, all others character fields. Values of the label column
+/// and numbers with leading zeros, like the code "007", stay character fields as written.
+///
+/// Returns the points with the number of rows left out for lack of a valid position.
+///
+/// # Panics
+/// Panics if the file cannot be read, or lacks the latitude or longitude column.
+#[must_use]
+pub fn read_csv_points(path: &Path, columns: &CsvColumns) -> (Vec<(Geometry<f64>, Record)>, usize) {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(columns.delimiter)
+        .flexible(true)
+        .from_path(path)
+        .unwrap_or_else(|_| panic!("Error loading CSV: {}", path.display()));
+    let headers: Vec<String> = reader
+        .headers()
+        .unwrap_or_else(|error| panic!("Error reading CSV header {}: {error}", path.display()))
+        .iter()
+        .map(|header| header.trim().to_string())
+        .collect();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name))
+            .unwrap_or_else(|| panic!("No column {name} in CSV: {}", path.display()))
+    };
+    let (lat_column, lon_column) = (column(columns.lat), column(columns.lon));
+    let label_column = columns.label.and_then(|label| {
+        headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(label))
+    });
+
+    let mut points = Vec::new();
+    let mut skipped = 0;
+    for row in reader.records() {
+        let row =
+            row.unwrap_or_else(|error| panic!("Error reading CSV {}: {error}", path.display()));
+        let coordinate = |index: usize| {
+            row.get(index)
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|value| value.is_finite())
+        };
+        let (Some(lat), Some(lon)) = (coordinate(lat_column), coordinate(lon_column)) else {
+            skipped += 1;
+            continue;
+        };
+
+        let mut record = Record::default();
+        for (index, (header, value)) in headers.iter().zip(row.iter()).enumerate() {
+            let value = value.trim();
+            let value = if Some(index) == label_column {
+                FieldValue::Character(Some(value.to_string()).filter(|value| !value.is_empty()))
+            } else {
+                field_value(value)
+            };
+            record.insert(header.clone(), value);
+        }
+        points.push((Geometry::Point(Point::new(lon, lat)), record));
+    }
+    (points, skipped)
+}
+
+/// Converts a CSV value to a numeric field if it is a finite number without leading zeros,
+/// a character field otherwise
+fn field_value(value: &str) -> FieldValue {
+    if value.is_empty() {
+        return FieldValue::Character(None);
+    }
+    let digits = value.trim_start_matches(['+', '-']);
+    let leading_zero = digits.len() > 1
+        && digits.starts_with('0')
+        && digits[1..].starts_with(|next: char| next.is_ascii_digit());
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() && !leading_zero => FieldValue::Numeric(Some(number)),
+        _ => FieldValue::Character(Some(value.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basemap::attributes;
+
+    /// Reads CSV text from a file of its own in the temporary directory
+    fn read(name: &str, text: &str, columns: &CsvColumns) -> (Vec<(Geometry<f64>, Record)>, usize) {
+        let path = std::env::temp_dir().join(format!("basemap-{}-{name}.csv", std::process::id()));
+        std::fs::write(&path, text).expect("Error writing test CSV");
+        let points = read_csv_points(&path, columns);
+        std::fs::remove_file(&path).expect("Error removing test CSV");
+        points
+    }
+
+    #[test]
+    fn keeps_a_numeric_label_column_as_text() {
+        let columns = CsvColumns {
+            label: Some("code"),
+            ..CsvColumns::default()
+        };
+        let (points, _) = read(
+            "label",
+            "latitude,longitude,code,id\n1,2,7,007\n3,4,007,NaN\n",
+            &columns,
+        );
+        let record = |index: usize| &points[index].1;
+        assert_eq!(attributes::text(record(0), "code"), Some("7"));
+        assert_eq!(attributes::text(record(1), "code"), Some("007"));
+        assert_eq!(attributes::text(record(0), "id"), Some("007"));
+        assert_eq!(attributes::text(record(1), "id"), Some("NaN"));
+    }
+
+    #[test]
+    fn converts_other_numbers_to_numeric_fields() {
+        assert!(
+            matches!(field_value("-12.5"), FieldValue::Numeric(Some(number)) if number < -12.0)
+        );
+        assert!(matches!(field_value("0.25"), FieldValue::Numeric(Some(_))));
+        assert!(matches!(field_value("0"), FieldValue::Numeric(Some(_))));
+        assert!(matches!(
+            field_value("-007"),
+            FieldValue::Character(Some(_))
+        ));
+        assert!(matches!(field_value("inf"), FieldValue::Character(Some(_))));
+        assert!(matches!(field_value(""), FieldValue::Character(None)));
+    }
+
+    #[test]
+    fn matches_headers_ignoring_case_and_spaces_with_any_delimiter() {
+        let columns = CsvColumns {
+            lat: "Lat",
+            lon: "LON",
+            label: Some("name"),
+            delimiter: b';',
+        };
+        let (points, skipped) = read(
+            "delimiter",
+            " lat ; lon ;Name\n48.5;\"2,25\";Paris\n48.5;2.25;\"Paris; France\"\n",
+            &columns,
+        );
+        assert_eq!((points.len(), skipped), (1, 1));
+        assert!(
+            matches!(points[0].0, Geometry::Point(point) if point.x() > 2.0 && point.y() > 48.0)
+        );
+        assert_eq!(
+            attributes::text(&points[0].1, "name"),
+            Some("Paris; France")
+        );
+    }
+
+    #[test]
+    fn counts_rows_without_a_valid_position() {
+        let (points, skipped) = read(
+            "skipped",
+            "name,latitude,longitude\nA,1,2\nB,,2\nC,NaN,2\nD,1\nE,1,x\n",
+            &CsvColumns::default(),
+        );
+        assert_eq!((points.len(), skipped), (1, 4));
+        assert_eq!(attributes::text(&points[0].1, "name"), Some("A"));
+    }
+}
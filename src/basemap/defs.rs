@@ -0,0 +1,249 @@
+//! SVG definitions used by the map layers
+//!
+//! Patterns, gradients and filters are emitted into the document `<defs>` and referenced from the drawn
+//! paths by an id derived from their content, so identical definitions share an id.
+//! The ids already in a document are tracked, so each definition is written once.
+
+use std::hash::{Hash, Hasher};
+
+use rustc_hash::{FxHashSet, FxHasher};
+use svg::{
+    Node,
+    node::{Blob, element},
+};
+
+use super::styles::{FillPattern, Filter, Gradient, GradientStop, LayerStyle};
+
+/// The ids of the definitions already written to a document
+#[derive(Default)]
+pub struct DefinedIds(FxHashSet<String>);
+
+/// Appends the definitions needed by a layer style to the document, skipping those already in it
+///
+/// # Arguments
+/// * `document` - The SVG document or group to modify
+/// * `layer_style` - The style whose patterns, gradients and filters should be defined
+/// * `defined` - The ids of the definitions already in the document, the new ones are added
+pub fn append_layer_defs(
+    document: &mut impl Node,
+    layer_style: &LayerStyle,
+    defined: &mut DefinedIds,
+) {
+    let definitions = [
+        layer_style.fill_pattern.as_ref().map(pattern_element),
+        layer_style.fill_gradient.as_ref().map(gradient_element),
+        layer_style.filter.as_ref().map(filter_element),
+    ];
+    let mut defs = element::Definitions::new();
+    let mut empty = true;
+    for (id, definition) in definitions.into_iter().flatten() {
+        if defined.0.insert(id) {
+            defs.append(definition);
+            empty = false;
+        }
+    }
+    if !empty {
+        document.append(defs);
+    }
+}
+
+/// Returns the fill paint of a layer style
+///
+/// This is a reference to the fill gradient if the style has one, otherwise the fill color.
+#[must_use]
+pub fn fill_paint(layer_style: &LayerStyle) -> String {
+    match &layer_style.fill_gradient {
+        Some(gradient) => format!(
+            "url(#{})",
+            content_id("gradient", &gradient_content(gradient))
+        ),
+        None => layer_style.fill.to_string(),
+    }
+}
+
+/// Returns the paint reference to a fill pattern (e.g. `url(#pattern-1f2e3d)`)
+#[must_use]
+pub fn pattern_url(pattern: &FillPattern) -> String {
+    format!("url(#{})", content_id("pattern", &pattern_content(pattern)))
+}
+
+/// Returns the reference to a filter (e.g. `url(#filter-1f2e3d)`)
+#[must_use]
+pub fn filter_url(filter: &Filter) -> String {
+    format!("url(#{})", content_id("filter", &filter_content(filter)))
+}
+
+/// Builds the `<pattern>` element of a fill pattern, including its id, and returns it with the id
+fn pattern_element(pattern: &FillPattern) -> (String, Box<dyn Node>) {
+    let content = pattern_content(pattern);
+    let id = content_id("pattern", &content);
+    (id.clone(), Box::new(content.set("id", id)))
+}
+
+/// Builds the `<pattern>` element of a fill pattern without an id
+fn pattern_content(pattern: &FillPattern) -> element::Pattern {
+    let pattern_element = element::Pattern::new().set("patternUnits", "userSpaceOnUse");
+    match *pattern {
+        FillPattern::Hatch {
+            angle,
+            spacing,
+            stroke,
+            stroke_width,
+        } => {
+            // A horizontal line through the middle of the tile, rotated as a whole
+            let line = element::Line::new()
+                .set("x1", 0)
+                .set("y1", spacing / 2.0)
+                .set("x2", spacing)
+                .set("y2", spacing / 2.0)
+                .set("stroke", stroke)
+                .set("stroke-width", stroke_width);
+            pattern_element
+                .set("width", spacing)
+                .set("height", spacing)
+                .set("patternTransform", format!("rotate({angle})"))
+                .add(line)
+        }
+        FillPattern::Dots {
+            spacing,
+            radius,
+            fill,
+        } => {
+            let dot = element::Circle::new()
+                .set("cx", spacing / 2.0)
+                .set("cy", spacing / 2.0)
+                .set("r", radius)
+                .set("fill", fill);
+            pattern_element
+                .set("width", spacing)
+                .set("height", spacing)
+                .add(dot)
+        }
+        FillPattern::Tile {
+            content,
+            width,
+            height,
+        } => pattern_element
+            .set("width", width)
+            .set("height", height)
+            .add(Blob::new(content)),
+    }
+}
+
+/// Builds the gradient element of a fill gradient, including its id, and returns it with the id
+fn gradient_element(gradient: &Gradient) -> (String, Box<dyn Node>) {
+    let mut content = gradient_content(gradient);
+    let id = content_id("gradient", &content);
+    if let Some(attributes) = content.get_attributes_mut() {
+        attributes.insert("id".to_string(), id.clone().into());
+    }
+    (id, content)
+}
+
+/// Builds the `<linearGradient>` or `<radialGradient>` element of a fill gradient without an id
+fn gradient_content(gradient: &Gradient) -> Box<dyn Node> {
+    match gradient {
+        Gradient::Linear {
+            x1,
+            y1,
+            x2,
+            y2,
+            stops,
+        } => {
+            let linear = element::LinearGradient::new()
+                .set("x1", *x1)
+                .set("y1", *y1)
+                .set("x2", *x2)
+                .set("y2", *y2);
+            Box::new(add_stops(linear, stops))
+        }
+        Gradient::Radial { cx, cy, r, stops } => {
+            let radial = element::RadialGradient::new()
+                .set("cx", *cx)
+                .set("cy", *cy)
+                .set("r", *r);
+            Box::new(add_stops(radial, stops))
+        }
+    }
+}
+
+/// Appends the color stops to a gradient element
+fn add_stops<T: Node>(mut gradient: T, stops: &[GradientStop]) -> T {
+    for stop in stops {
+        gradient.append(
+            element::Stop::new()
+                .set("offset", stop.offset)
+                .set("stop-color", stop.color)
+                .set("stop-opacity", stop.opacity),
+        );
+    }
+    gradient
+}
+
+/// Builds the `<filter>` element of a filter effect, including its id, and returns it with the id
+fn filter_element(filter: &Filter) -> (String, Box<dyn Node>) {
+    let content = filter_content(filter);
+    let id = content_id("filter", &content);
+    (id.clone(), Box::new(content.set("id", id)))
+}
+
+/// Builds the `<filter>` element of a filter effect without an id
+///
+/// The drop shadow is composed from basic primitives rather than `feDropShadow`,
+/// so it renders the same in resvg and in older SVG viewers.
+fn filter_content(filter: &Filter) -> element::Filter {
+    match *filter {
+        Filter::DropShadow {
+            dx,
+            dy,
+            blur,
+            color,
+            opacity,
+        } => element::Filter::new()
+            .set("x", "-50%")
+            .set("y", "-50%")
+            .set("width", "200%")
+            .set("height", "200%")
+            .add(
+                element::FilterEffectGaussianBlur::new()
+                    .set("in", "SourceAlpha")
+                    .set("stdDeviation", blur)
+                    .set("result", "blur"),
+            )
+            .add(
+                element::FilterEffectOffset::new()
+                    .set("in", "blur")
+                    .set("dx", dx)
+                    .set("dy", dy)
+                    .set("result", "offset"),
+            )
+            .add(
+                element::FilterEffectFlood::new()
+                    .set("flood-color", color)
+                    .set("flood-opacity", opacity),
+            )
+            .add(
+                element::FilterEffectComposite::new()
+                    .set("in2", "offset")
+                    .set("operator", "in")
+                    .set("result", "shadow"),
+            )
+            .add(
+                element::FilterEffectMerge::new()
+                    .add(element::FilterEffectMergeNode::new().set("in", "shadow"))
+                    .add(element::FilterEffectMergeNode::new().set("in", "SourceGraphic")),
+            ),
+        Filter::Blur { std_deviation } => element::Filter::new().add(
+            element::FilterEffectGaussianBlur::new()
+                .set("in", "SourceGraphic")
+                .set("stdDeviation", std_deviation),
+        ),
+    }
+}
+
+/// Derives a stable id from the rendered content of a definition
+fn content_id(prefix: &str, content: &impl std::fmt::Display) -> String {
+    let mut hasher = FxHasher::default();
+    content.to_string().hash(&mut hasher);
+    format!("{prefix}-{:x}", hasher.finish())
+}
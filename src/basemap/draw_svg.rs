@@ -3,18 +3,33 @@
 //! This module provides functions for rendering the Natural Earth basemap to SVG.
 //! It handles the conversion of shapefile data into SVG paths and applies the appropriate styles.
 
-use super::styles::{Layer, LayerStyle};
-use svg::{Document, Node, node::element};
+use super::defs::{DefinedIds, append_layer_defs, fill_paint, pattern_url};
+use super::styles::{LabelStyle, Layer, LayerStyle, MarkerShape, MarkerStyle, StrokePass};
+use svg::{Node, node::element};
 
 use super::Map;
 
+/// The radius of the inner corners of a star marker relative to its outer corners
+const STAR_INNER_RATIO: f64 = 0.382;
+
 /// Sets the background of the map using the specified layer
 ///
+/// The background is filled with the gradient of the layer style if it has one,
+/// e.g. an ocean darkening toward the edges or the glow of an atmosphere.
+///
 /// # Arguments
 /// * `map` - The map dimensions and projection settings
-/// * `document` - The SVG document to modify
+/// * `document` - The SVG document or group to modify
 /// * `layer` - The layer to use for the background (typically ocean)
-pub fn set_background(map: &Map, document: &mut Document, layer: &Layer) {
+/// * `defined` - The ids of the definitions already in the document
+pub fn set_background(
+    map: &Map,
+    document: &mut impl Node,
+    layer: &Layer,
+    defined: &mut DefinedIds,
+) {
+    append_layer_defs(document, &layer.layer_style, defined);
+
     let data = element::path::Data::new();
     let data = data
         .move_to((0, 0))
@@ -24,7 +39,7 @@ pub fn set_background(map: &Map, document: &mut Document, layer: &Layer) {
 
     let path = element::Path::new()
         .set("stroke", layer.layer_style.stroke)
-        .set("fill", layer.layer_style.fill)
+        .set("fill", fill_paint(&layer.layer_style))
         .set("fill-opacity", layer.layer_style.fill_opacity)
         .set("d", data);
     document.append(path);
@@ -32,14 +47,19 @@ pub fn set_background(map: &Map, document: &mut Document, layer: &Layer) {
 
 /// Draws a polygon as an SVG path
 ///
+/// If the style has a fill pattern, it is drawn on top of the fill color.
+/// The pattern must already be defined in the document
+/// (see [`append_layer_defs`](super::defs::append_layer_defs)).
+/// The stroke passes of the style are drawn for the whole layer, see [`stroke_pass_groups`].
+///
 /// # Arguments
 /// * `pts` - The points defining the polygon's vertices
-/// * `document` - The SVG document to modify
+/// * `document` - The SVG document or group to modify
 /// * `layer_style` - The style to apply to the polygon
 /// * `data` - The initial path data to build upon
 pub fn draw_polygon(
     pts: &[(f64, f64)],
-    document: &mut Document,
+    document: &mut impl Node,
     layer_style: &LayerStyle,
     data: element::path::Data,
 ) {
@@ -48,26 +68,47 @@ pub fn draw_polygon(
         data.line_to((position.0, position.1))
     });
 
-    let path = element::Path::new()
-        .set("fill", layer_style.fill)
+    let mut path = element::Path::new()
+        .set("fill", fill_paint(layer_style))
         .set("fill-opacity", layer_style.fill_opacity)
         .set("stroke", layer_style.stroke)
-        .set("stroke-width", layer_style.stroke_width)
-        .set("d", data);
+        .set("stroke-width", layer_style.stroke_width);
+    set_stroke_options(
+        &mut path,
+        layer_style.stroke_dasharray,
+        layer_style.stroke_linecap,
+        layer_style.stroke_linejoin,
+    );
+    set_optional(
+        &mut path,
+        "stroke-miterlimit",
+        layer_style.stroke_miterlimit,
+    );
+    path.assign("d", data.clone());
 
     document.append(path);
+
+    if let Some(pattern) = &layer_style.fill_pattern {
+        let pattern_path = element::Path::new()
+            .set("fill", pattern_url(pattern))
+            .set("stroke", "none")
+            .set("d", data);
+        document.append(pattern_path);
+    }
 }
 
 /// Draws a polyline as an SVG path
 ///
+/// The stroke passes of the style are drawn for the whole layer, see [`stroke_pass_groups`].
+///
 /// # Arguments
 /// * `pts` - The points defining the polyline's vertices
-/// * `document` - The SVG document to modify
+/// * `document` - The SVG document or group to modify
 /// * `layer_style` - The style to apply to the polyline
 /// * `data` - The initial path data to build upon
 pub fn draw_polyline(
     pts: &[(f64, f64)],
-    document: &mut Document,
+    document: &mut impl Node,
     layer_style: &LayerStyle,
     data: element::path::Data,
 ) {
@@ -76,29 +117,101 @@ pub fn draw_polyline(
         data.line_to((position.0, position.1))
     });
 
-    let path = element::Path::new()
-        .set("fill", layer_style.fill)
+    let mut path = element::Path::new()
+        .set("fill", fill_paint(layer_style))
         .set("fill-opacity", layer_style.fill_opacity)
         .set("stroke", layer_style.stroke)
-        .set("stroke-width", layer_style.stroke_width)
-        .set("stroke-linejoin", "round")
-        .set("d", data);
+        .set("stroke-width", layer_style.stroke_width);
+    set_stroke_options(
+        &mut path,
+        layer_style.stroke_dasharray,
+        layer_style.stroke_linecap,
+        Some(layer_style.stroke_linejoin.unwrap_or("round")),
+    );
+    set_optional(
+        &mut path,
+        "stroke-miterlimit",
+        layer_style.stroke_miterlimit,
+    );
+    path.assign("d", data);
 
     document.append(path);
 }
 
+/// Returns one group per stroke pass of a layer, in order, carrying the stroke of its pass
+///
+/// The outlines of all features of a layer go into the groups (see [`add_to_stroke_passes`]),
+/// which are drawn before the main strokes of the layer, so a casing runs unbroken
+/// beneath all the lines it joins instead of cutting across the lines drawn before it.
+#[must_use]
+pub fn stroke_pass_groups(stroke_passes: &[StrokePass]) -> Vec<element::Group> {
+    stroke_passes
+        .iter()
+        .map(|pass| {
+            let mut group = element::Group::new()
+                .set("fill", "none")
+                .set("stroke", pass.stroke)
+                .set("stroke-width", pass.stroke_width);
+            set_stroke_options(
+                &mut group,
+                pass.stroke_dasharray,
+                pass.stroke_linecap,
+                pass.stroke_linejoin,
+            );
+            group
+        })
+        .collect()
+}
+
+/// Adds the outline through the points of a feature to each stroke pass group
+///
+/// # Arguments
+/// * `pass_groups` - The groups of the stroke passes, see [`stroke_pass_groups`]
+/// * `pts` - The points of the polyline or polygon ring
+pub fn add_to_stroke_passes(pass_groups: &mut [element::Group], pts: &[(f64, f64)]) {
+    let Some((first, rest)) = pts.split_first() else {
+        return;
+    };
+    let data = rest.iter().fold(
+        element::path::Data::new().move_to(*first),
+        |data, position| data.line_to(*position),
+    );
+    for group in pass_groups {
+        group.append(element::Path::new().set("d", data.clone()));
+    }
+}
+
+/// Sets the dash pattern, line cap and line join of a path or group where they are given
+fn set_stroke_options(
+    node: &mut impl Node,
+    dasharray: Option<&str>,
+    linecap: Option<&str>,
+    linejoin: Option<&str>,
+) {
+    set_optional(node, "stroke-dasharray", dasharray);
+    set_optional(node, "stroke-linecap", linecap);
+    set_optional(node, "stroke-linejoin", linejoin);
+}
+
+/// Sets an attribute on a path or group only if a value is given
+fn set_optional(node: &mut impl Node, name: &str, value: Option<&str>) {
+    if let Some(value) = value {
+        node.assign(name, value);
+    }
+}
+
 /// Draws text at a specified location on the map
 ///
 /// # Arguments
 /// * `position` - The (x,y) coordinates where the text should be placed
 /// * `text` - The text string to draw
-/// * `document` - The SVG document to modify
+/// * `document` - The SVG document or group to modify
 /// * `font_size` - The font size in pixels
 /// * `fill` - The text color
 pub fn draw_text(
     position: (f64, f64),
     text: &str,
-    document: &mut Document,
+    document: &mut impl Node,
     font_size: u32,
     fill: &str,
 ) {
@@ -112,3 +225,181 @@ pub fn draw_text(
 
     document.append(text_element);
 }
+
+/// Draws a point marker centered on a position
+///
+/// # Arguments
+/// * `position` - The (x,y) coordinates of the center of the marker
+/// * `size` - The width and height of the marker in pixels
+/// * `document` - The SVG document or group to modify
+/// * `marker_style` - The shape and colors of the marker
+pub fn draw_marker(
+    position: (f64, f64),
+    size: f64,
+    document: &mut impl Node,
+    marker_style: &MarkerStyle,
+) {
+    let (x, y) = position;
+    let radius = size / 2.0;
+    let mut marker: Box<dyn Node> = match marker_style.shape {
+        MarkerShape::Circle => Box::new(
+            element::Circle::new()
+                .set("cx", x)
+                .set("cy", y)
+                .set("r", radius),
+        ),
+        MarkerShape::Square => Box::new(
+            element::Rectangle::new()
+                .set("x", x - radius)
+                .set("y", y - radius)
+                .set("width", size)
+                .set("height", size),
+        ),
+        MarkerShape::Triangle => {
+            // Equilateral, with its centroid on the position
+            let half_side = radius * 3f64.sqrt() / 2.0;
+            let points = [
+                (x, y - radius),
+                (x + half_side, y + radius / 2.0),
+                (x - half_side, y + radius / 2.0),
+            ];
+            Box::new(element::Polygon::new().set("points", polygon_points(&points)))
+        }
+        MarkerShape::Star => {
+            // Alternating outer and inner corners, starting at the top
+            let points: Vec<(f64, f64)> = (0..10)
+                .map(|index| {
+                    let corner_radius = if index % 2 == 0 {
+                        radius
+                    } else {
+                        radius * STAR_INNER_RATIO
+                    };
+                    let angle = f64::from(index) * std::f64::consts::PI / 5.0;
+                    (
+                        x + corner_radius * angle.sin(),
+                        y - corner_radius * angle.cos(),
+                    )
+                })
+                .collect();
+            Box::new(element::Polygon::new().set("points", polygon_points(&points)))
+        }
+        MarkerShape::Icon(href) => {
+            let image = element::Image::new()
+                .set("href", href)
+                .set("x", x - radius)
+                .set("y", y - radius)
+                .set("width", size)
+                .set("height", size);
+            document.append(image);
+            return;
+        }
+    };
+
+    if let Some(attributes) = marker.get_attributes_mut() {
+        attributes.insert("fill".to_string(), marker_style.fill.into());
+        attributes.insert("fill-opacity".to_string(), marker_style.fill_opacity.into());
+        attributes.insert("stroke".to_string(), marker_style.stroke.into());
+        attributes.insert("stroke-width".to_string(), marker_style.stroke_width.into());
+    }
+    document.append(marker);
+}
+
+/// Formats points as the `points` attribute of an SVG polygon
+fn polygon_points(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .map(|(x, y)| format!("{x},{y}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Draws a label with the font, halo and anchor of a label style
+///
+/// The halo is drawn as a separate stroked copy of the text beneath it.
+///
+/// # Arguments
+/// * `position` - The (x,y) coordinates of the text anchor on the baseline
+/// * `text` - The text string to draw, already transformed (e.g. upper case)
+/// * `document` - The SVG document or group to modify
+/// * `label_style` - The style of the label
+/// * `fill` - The text color
+pub fn draw_label(
+    position: (f64, f64),
+    text: &str,
+    document: &mut impl Node,
+    label_style: &LabelStyle,
+    fill: &str,
+) {
+    let text_element = font_attributes(element::Text::new(text), label_style)
+        .set("x", position.0)
+        .set("y", position.1)
+        .set("text-anchor", label_style.text_anchor);
+
+    append_with_halo(text_element, document, label_style, fill);
+}
+
+/// Draws a label along a path defined elsewhere in the document
+///
+/// The text is centered on the path, both along it and across it.
+///
+/// # Arguments
+/// * `path_id` - The id of the path the text follows
+/// * `text` - The text string to draw, already transformed (e.g. upper case)
+/// * `document` - The SVG document or group to modify
+/// * `label_style` - The style of the label
+/// * `fill` - The text color
+pub fn draw_line_label(
+    path_id: &str,
+    text: &str,
+    document: &mut impl Node,
+    label_style: &LabelStyle,
+    fill: &str,
+) {
+    let text_path = element::TextPath::new(text)
+        .set("href", format!("#{path_id}"))
+        .set("startOffset", "50%");
+    let text_element = font_attributes(element::Text::new(""), label_style)
+        .set("text-anchor", "middle")
+        .set("dy", "0.35em")
+        .add(text_path);
+
+    append_with_halo(text_element, document, label_style, fill);
+}
+
+/// Sets the font family, size, weight, style and letter spacing of a text element
+fn font_attributes(text_element: element::Text, label_style: &LabelStyle) -> element::Text {
+    let text_element = text_element
+        .set("font-family", label_style.font_family)
+        .set("font-size", label_style.font_size)
+        .set("font-weight", label_style.font_weight);
+    let text_element = if label_style.italic {
+        text_element.set("font-style", "italic")
+    } else {
+        text_element
+    };
+    if label_style.letter_spacing == 0.0 {
+        text_element
+    } else {
+        text_element.set("letter-spacing", label_style.letter_spacing)
+    }
+}
+
+/// Appends a text element with its fill, preceded by a stroked copy if the style has a halo
+fn append_with_halo(
+    text_element: element::Text,
+    document: &mut impl Node,
+    label_style: &LabelStyle,
+    fill: &str,
+) {
+    if let Some(halo_color) = label_style.halo_color {
+        let halo = text_element
+            .clone()
+            .set("fill", "none")
+            .set("stroke", halo_color)
+            .set("stroke-width", 2.0 * label_style.halo_width)
+            .set("stroke-linejoin", "round");
+        document.append(halo);
+    }
+
+    document.append(text_element.set("fill", fill));
+}
@@ -0,0 +1,79 @@
+//! Layer effects built from the projected geometry of a layer
+
+use i_overlay::mesh::outline::offset::OutlineOffset;
+use i_overlay::mesh::style::{LineJoin, OutlineStyle};
+use svg::{Node, node::element};
+
+use super::styles::WaterLines;
+
+/// Draws water lines around a set of polygon outlines
+///
+/// The outlines are buffered outward together, so lines of neighbouring polygons merge.
+/// The nearest line is drawn with the full opacity, each further line fades linearly toward zero.
+///
+/// # Arguments
+/// * `rings` - The outer rings of the polygons in pixel coordinates
+/// * `document` - The SVG document or group to modify
+/// * `water_lines` - The distances and style of the water lines
+pub fn draw_water_lines(
+    rings: &[Vec<(f64, f64)>],
+    document: &mut impl Node,
+    water_lines: &WaterLines,
+) {
+    let contours: Vec<Vec<[f64; 2]>> = rings
+        .iter()
+        .filter(|ring| ring.len() >= 3)
+        .map(|ring| clockwise(ring))
+        .collect();
+    if contours.is_empty() {
+        return;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let count = water_lines.distances.len() as f64;
+    // Draw the furthest line first so nearer lines stay on top
+    for (index, distance) in water_lines.distances.iter().enumerate().rev() {
+        #[allow(clippy::cast_precision_loss)]
+        let opacity = water_lines.opacity * (1.0 - index as f64 / count);
+        let style = OutlineStyle::new(*distance).line_join(LineJoin::Round(0.25));
+
+        let mut data = element::path::Data::new();
+        for shape in contours.outline(style) {
+            for contour in shape {
+                let Some(first) = contour.first() else {
+                    continue;
+                };
+                data = data.move_to((first[0], first[1]));
+                data = contour
+                    .iter()
+                    .skip(1)
+                    .fold(data, |data, point| data.line_to((point[0], point[1])));
+                data = data.close();
+            }
+        }
+
+        let path = element::Path::new()
+            .set("fill", "none")
+            .set("stroke", water_lines.stroke)
+            .set("stroke-width", water_lines.stroke_width)
+            .set("stroke-opacity", opacity)
+            .set("stroke-linejoin", "round")
+            .set("d", data);
+        document.append(path);
+    }
+}
+
+/// Returns the ring as a contour wound clockwise, the orientation of outer contours
+fn clockwise(ring: &[(f64, f64)]) -> Vec<[f64; 2]> {
+    let twice_area: f64 = ring
+        .iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+        .sum();
+    let contour = ring.iter().map(|point| [point.0, point.1]);
+    if twice_area > 0.0 {
+        contour.rev().collect()
+    } else {
+        contour.collect()
+    }
+}
@@ -0,0 +1,189 @@
+//! This module provides functionality for drawing the print frame around the map.
+
+use svg::{Node, node::element};
+
+use super::Map;
+use super::draw_svg::draw_label;
+use super::graticule::{format_lat, format_lon, multiples};
+use super::labels::DIGIT_HEIGHT;
+use super::styles::{Frame, Graticule, LabelStyle};
+use super::utils::mapping_function;
+
+/// Distance in pixels between the annotations and the tick marks
+const ANNOTATION_PADDING: f64 = 2.0;
+
+/// Draws the frame around the map
+///
+/// The frame lies outside the map area, so the document needs a margin of at least
+/// the width of the frame to show it.
+///
+/// # Arguments
+/// * `map` - The map dimensions and projection settings
+/// * `document` - The SVG document or group to modify
+/// * `frame` - The style of the frame
+/// * `graticule` - The graticule whose intervals the ladder, ticks and annotations follow
+pub fn draw_frame(map: &Map, document: &mut impl Node, frame: &Frame, graticule: &Graticule) {
+    let width = f64::from(map.cols);
+    let height = f64::from(map.rows);
+
+    if frame.ladder_width > 0.0 {
+        draw_ladder(map, document, frame, graticule);
+    }
+
+    document.append(
+        rectangle((0.0, 0.0), (width, height))
+            .set("fill", "none")
+            .set("stroke", frame.color)
+            .set("stroke-width", frame.neatline_width),
+    );
+
+    // Ticks and annotations start at the outside of the ladder
+    let inner = frame.ladder_width;
+    let outer = inner + frame.tick_length;
+    let meridians: Vec<(f64, f64)> = multiples((map.lon_min, map.lon_max), graticule.lon_interval)
+        .into_iter()
+        .map(|lon| (lon, mapping_function(lon, map.lat_max, map).0))
+        .filter(|(_, x)| (0.0..=width).contains(x))
+        .collect();
+    let parallels: Vec<(f64, f64)> = multiples((map.lat_min, map.lat_max), graticule.lat_interval)
+        .into_iter()
+        .map(|lat| (lat, mapping_function(map.lon_min, lat, map).1))
+        .filter(|(_, y)| (0.0..=height).contains(y))
+        .collect();
+
+    if frame.tick_length > 0.0 {
+        for (_, x) in &meridians {
+            draw_tick(document, frame, (*x, -inner), (*x, -outer));
+            draw_tick(document, frame, (*x, height + inner), (*x, height + outer));
+        }
+        for (_, y) in &parallels {
+            draw_tick(document, frame, (-inner, *y), (-outer, *y));
+            draw_tick(document, frame, (width + inner, *y), (width + outer, *y));
+        }
+    }
+
+    let Some(label_style) = &frame.annotations else {
+        return;
+    };
+    let fill = label_style.fill.unwrap_or(frame.color);
+    let font_size = label_style.font_size;
+    let distance = outer + ANNOTATION_PADDING;
+
+    let centered = LabelStyle {
+        text_anchor: "middle",
+        ..label_style.clone()
+    };
+    for (lon, x) in &meridians {
+        let text = format_lon(*lon);
+        draw_label((*x, -distance), &text, document, &centered, fill);
+        let bottom = height + distance + DIGIT_HEIGHT * font_size;
+        draw_label((*x, bottom), &text, document, &centered, fill);
+    }
+
+    let left = LabelStyle {
+        text_anchor: "end",
+        ..label_style.clone()
+    };
+    let right = LabelStyle {
+        text_anchor: "start",
+        ..label_style.clone()
+    };
+    for (lat, y) in &parallels {
+        let text = format_lat(*lat);
+        let baseline = y + DIGIT_HEIGHT * font_size / 2.0;
+        draw_label((-distance, baseline), &text, document, &left, fill);
+        draw_label((width + distance, baseline), &text, document, &right, fill);
+    }
+}
+
+/// Draws the bars of alternating colors along all four edges and their outline
+fn draw_ladder(map: &Map, document: &mut impl Node, frame: &Frame, graticule: &Graticule) {
+    let width = f64::from(map.cols);
+    let height = f64::from(map.rows);
+    let ladder = frame.ladder_width;
+
+    let lon_interval = graticule
+        .minor_lon_interval
+        .unwrap_or(graticule.lon_interval);
+    let lat_interval = graticule
+        .minor_lat_interval
+        .unwrap_or(graticule.lat_interval);
+    let xs = stops(
+        multiples((map.lon_min, map.lon_max), lon_interval)
+            .into_iter()
+            .map(|lon| mapping_function(lon, map.lat_max, map).0),
+        width,
+    );
+    let ys = stops(
+        multiples((map.lat_min, map.lat_max), lat_interval)
+            .into_iter()
+            .map(|lat| mapping_function(map.lon_min, lat, map).1),
+        height,
+    );
+
+    for (index, pair) in xs.windows(2).enumerate() {
+        let color = ladder_color(frame, index);
+        let size = (pair[1] - pair[0], ladder);
+        document.append(rectangle((pair[0], -ladder), size).set("fill", color));
+        document.append(rectangle((pair[0], height), size).set("fill", color));
+    }
+    for (index, pair) in ys.windows(2).enumerate() {
+        let color = ladder_color(frame, index);
+        let size = (ladder, pair[1] - pair[0]);
+        document.append(rectangle((-ladder, pair[0]), size).set("fill", color));
+        document.append(rectangle((width, pair[0]), size).set("fill", color));
+    }
+
+    document.append(
+        rectangle(
+            (-ladder, -ladder),
+            (width + 2.0 * ladder, height + 2.0 * ladder),
+        )
+        .set("fill", "none")
+        .set("stroke", frame.color)
+        .set("stroke-width", frame.neatline_width / 2.0),
+    );
+}
+
+/// Draws a tick mark between two points
+fn draw_tick(document: &mut impl Node, frame: &Frame, from: (f64, f64), to: (f64, f64)) {
+    let line = element::Line::new()
+        .set("x1", from.0)
+        .set("y1", from.1)
+        .set("x2", to.0)
+        .set("y2", to.1)
+        .set("stroke", frame.color)
+        .set("stroke-width", frame.neatline_width / 2.0);
+    document.append(line);
+}
+
+/// Returns the color of the ladder bar with the given index
+fn ladder_color<'a>(frame: &Frame<'a>, index: usize) -> &'a str {
+    if index.is_multiple_of(2) {
+        frame.ladder_colors.0
+    } else {
+        frame.ladder_colors.1
+    }
+}
+
+/// Returns the sorted pixel positions where the ladder bars change color,
+/// including both ends of the edge
+fn stops(positions: impl Iterator<Item = f64>, length: f64) -> Vec<f64> {
+    let mut stops: Vec<f64> = positions
+        .filter(|position| *position > 0.0 && *position < length)
+        .collect();
+    stops.push(0.0);
+    stops.push(length);
+    stops.sort_by(f64::total_cmp);
+    stops.dedup();
+    stops
+}
+
+/// Returns a rectangle with the given top left corner and size
+fn rectangle(position: (f64, f64), size: (f64, f64)) -> element::Rectangle {
+    element::Rectangle::new()
+        .set("x", position.0)
+        .set("y", position.1)
+        .set("width", size.0)
+        .set("height", size.1)
+}
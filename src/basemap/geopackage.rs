@@ -0,0 +1,540 @@
+//! This module provides functionality for reading the features of a table of a `GeoPackage`.
+//!
+//! A `GeoPackage` is a `SQLite` database holding many layers in tables, like the
+//! `natural_earth_vector.gpkg` that Natural Earth is distributed as. The geometries
+//! are stored as blobs of a small `GeoPackage` header followed by well-known binary.
+
+use std::path::Path;
+
+use geo::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon,
+    Point, Polygon,
+};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use shapefile::dbase::{FieldValue, Record};
+
+/// Reads the features of a table of a `GeoPackage` with the other columns as records
+///
+/// The coordinates of the table are expected in degrees of longitude and latitude,
+/// as in the Natural Earth `GeoPackage`. Features with an empty or missing geometry are left out,
+/// as are columns holding blobs.
+///
+/// Returns the features with the number of features skipped for a malformed geometry blob.
+///
+/// # Panics
+/// Panics if the file cannot be opened or the table is no feature table of the `GeoPackage`.
+#[must_use]
+pub fn read_geopackage(path: &Path, table: &str) -> (Vec<(Geometry<f64>, Record)>, usize) {
+    let mut skipped = 0;
+    let features = geopackage_features(path, table)
+        .into_iter()
+        .filter_map(|(geometry, record)| {
+            if geometry.is_none() {
+                skipped += 1;
+            }
+            Some((geometry?, record))
+        })
+        .collect();
+    (features, skipped)
+}
+
+/// Reads the features of a table of a `GeoPackage`, with `None` for a malformed geometry
+///
+/// See [`read_geopackage`].
+///
+/// # Panics
+/// Panics if the file cannot be opened or the table is no feature table of the `GeoPackage`.
+#[must_use]
+pub fn geopackage_features(path: &Path, table: &str) -> Vec<(Option<Geometry<f64>>, Record)> {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .unwrap_or_else(|_| panic!("Error loading GeoPackage: {}", path.display()));
+    let geometry_column: String = connection
+        .query_row(
+            "SELECT column_name FROM gpkg_geometry_columns WHERE table_name = ?1",
+            [table],
+            |row| row.get(0),
+        )
+        .unwrap_or_else(|_| panic!("No feature table {table} in GeoPackage: {}", path.display()));
+
+    let mut statement = connection
+        .prepare(&format!("SELECT * FROM \"{}\"", table.replace('"', "\"\"")))
+        .expect("Error querying GeoPackage table");
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let mut rows = statement
+        .query([])
+        .expect("Error querying GeoPackage table");
+
+    let mut features = Vec::new();
+    while let Some(row) = rows.next().expect("Error reading data from GeoPackage") {
+        let mut geometry = Ok(None);
+        let mut record = Record::default();
+        for (index, column) in columns.iter().enumerate() {
+            let value = row
+                .get_ref(index)
+                .expect("Error reading data from GeoPackage");
+            if *column == geometry_column {
+                if let ValueRef::Blob(blob) = value {
+                    geometry = parse_geometry(blob);
+                }
+            } else if let Some(field_value) = field_value(value) {
+                record.insert(column.clone(), field_value);
+            }
+        }
+        match geometry {
+            Ok(Some(geometry)) => features.push((Some(geometry), record)),
+            Ok(None) => {}
+            Err(MalformedGeometry) => features.push((None, record)),
+        }
+    }
+    features
+}
+
+/// Converts a column value to a field value, `None` for blobs
+#[allow(clippy::cast_precision_loss)]
+fn field_value(value: ValueRef) -> Option<FieldValue> {
+    match value {
+        ValueRef::Null => Some(FieldValue::Character(None)),
+        ValueRef::Integer(integer) => Some(FieldValue::Numeric(Some(integer as f64))),
+        ValueRef::Real(real) => Some(FieldValue::Numeric(Some(real))),
+        ValueRef::Text(text) => Some(FieldValue::Character(Some(
+            String::from_utf8_lossy(text).into_owned(),
+        ))),
+        ValueRef::Blob(_) => None,
+    }
+}
+
+/// A geometry blob that is no valid `GeoPackage` geometry
+#[derive(PartialEq, Eq, Debug)]
+struct MalformedGeometry;
+
+/// Parses a `GeoPackage` geometry blob, `None` for an empty geometry
+fn parse_geometry(blob: &[u8]) -> Result<Option<Geometry<f64>>, MalformedGeometry> {
+    let [b'G', b'P', _, flags, ..] = *blob else {
+        return Err(MalformedGeometry);
+    };
+    if flags & 0b1_0000 != 0 {
+        return Ok(None);
+    }
+    // The envelope of the geometry follows the header, its size given by the flags
+    let envelope = match (flags >> 1) & 0b111 {
+        0 => 0,
+        1 => 32,
+        2 | 3 => 48,
+        4 => 64,
+        _ => return Err(MalformedGeometry),
+    };
+    let mut wkb = Wkb {
+        bytes: blob.get(8 + envelope..).ok_or(MalformedGeometry)?,
+        offset: 0,
+        little_endian: true,
+    };
+    wkb.geometry().map(Some).ok_or(MalformedGeometry)
+}
+
+/// A reader of well-known binary geometries
+struct Wkb<'b> {
+    bytes: &'b [u8],
+    offset: usize,
+    little_endian: bool,
+}
+
+impl Wkb<'_> {
+    /// Reads a geometry with its byte order and type
+    ///
+    /// Z and M values, given by ISO type codes, are read past and dropped.
+    fn geometry(&mut self) -> Option<Geometry<f64>> {
+        self.little_endian = match self.byte()? {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+        let code = self.u32()?;
+        let dimensions = match code / 1000 {
+            0 => 2,
+            1 | 2 => 3,
+            3 => 4,
+            _ => return None,
+        };
+        let geometry = match code % 1000 {
+            1 => Geometry::Point(Point(self.coord(dimensions)?)),
+            2 => Geometry::LineString(self.line_string(dimensions)?),
+            3 => Geometry::Polygon(self.polygon(dimensions)?),
+            4 => Geometry::MultiPoint(MultiPoint::new(self.members(
+                |wkb| match wkb.geometry()? {
+                    Geometry::Point(point) => Some(point),
+                    _ => None,
+                },
+            )?)),
+            5 => Geometry::MultiLineString(MultiLineString::new(self.members(|wkb| {
+                match wkb.geometry()? {
+                    Geometry::LineString(line_string) => Some(line_string),
+                    _ => None,
+                }
+            })?)),
+            6 => {
+                Geometry::MultiPolygon(MultiPolygon::new(self.members(
+                    |wkb| match wkb.geometry()? {
+                        Geometry::Polygon(polygon) => Some(polygon),
+                        _ => None,
+                    },
+                )?))
+            }
+            7 => Geometry::GeometryCollection(GeometryCollection::new_from(
+                self.members(Wkb::geometry)?,
+            )),
+            _ => return None,
+        };
+        Some(geometry)
+    }
+
+    /// Reads a polygon as its rings, the first being the exterior
+    fn polygon(&mut self, dimensions: usize) -> Option<Polygon<f64>> {
+        let mut rings = self.members(|wkb| wkb.line_string(dimensions))?.into_iter();
+        let exterior = rings.next().unwrap_or_else(|| LineString::new(Vec::new()));
+        Some(Polygon::new(exterior, rings.collect()))
+    }
+
+    /// Reads a line string as its points
+    fn line_string(&mut self, dimensions: usize) -> Option<LineString<f64>> {
+        self.members(|wkb| wkb.coord(dimensions))
+            .map(LineString::new)
+    }
+
+    /// Reads a count followed by as many members
+    fn members<T>(&mut self, mut member: impl FnMut(&mut Self) -> Option<T>) -> Option<Vec<T>> {
+        let count = self.u32()?;
+        (0..count).map(|_| member(self)).collect()
+    }
+
+    /// Reads the x and y of a position, skipping its other dimensions
+    fn coord(&mut self, dimensions: usize) -> Option<Coord<f64>> {
+        let x = self.f64()?;
+        let y = self.f64()?;
+        for _ in 2..dimensions {
+            self.f64()?;
+        }
+        Some(Coord { x, y })
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.offset)?;
+        self.offset += 1;
+        Some(byte)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.take()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        let bytes = self.take()?;
+        Some(if self.little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+
+    /// Reads the next bytes into an array
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + N)?
+            .try_into()
+            .ok()?;
+        self.offset += N;
+        Some(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::{line_string, point, polygon};
+
+    /// Writes well-known binary in either byte order
+    struct Writer {
+        bytes: Vec<u8>,
+        little_endian: bool,
+    }
+
+    impl Writer {
+        fn new(little_endian: bool) -> Self {
+            Writer {
+                bytes: Vec::new(),
+                little_endian,
+            }
+        }
+
+        /// Writes the byte order and type code of a geometry
+        fn header(&mut self, code: u32) -> &mut Self {
+            self.bytes.push(u8::from(self.little_endian));
+            self.u32(code)
+        }
+
+        fn u32(&mut self, value: u32) -> &mut Self {
+            let bytes = if self.little_endian {
+                value.to_le_bytes()
+            } else {
+                value.to_be_bytes()
+            };
+            self.bytes.extend(bytes);
+            self
+        }
+
+        /// Writes the coordinates of a position
+        fn position(&mut self, position: &[f64]) -> &mut Self {
+            for value in position {
+                let bytes = if self.little_endian {
+                    value.to_le_bytes()
+                } else {
+                    value.to_be_bytes()
+                };
+                self.bytes.extend(bytes);
+            }
+            self
+        }
+
+        /// Writes a count followed by as many positions
+        fn positions(&mut self, positions: &[&[f64]]) -> &mut Self {
+            self.u32(u32::try_from(positions.len()).expect("Few positions"));
+            for position in positions {
+                self.position(position);
+            }
+            self
+        }
+
+        fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+            self.bytes.extend(bytes);
+            self
+        }
+    }
+
+    /// Wraps well-known binary in a `GeoPackage` header with an envelope of the given code
+    fn blob(envelope_code: u8, wkb: &[u8]) -> Vec<u8> {
+        let envelope = match envelope_code {
+            0 => 0,
+            1 => 32,
+            2 | 3 => 48,
+            _ => 64,
+        };
+        let mut blob = vec![b'G', b'P', 0, envelope_code << 1 | 1, 0, 0, 0, 0];
+        blob.extend(std::iter::repeat_n(0, envelope));
+        blob.extend(wkb);
+        blob
+    }
+
+    /// Parses well-known binary wrapped in a header without an envelope
+    fn parse(wkb: &Writer) -> Result<Option<Geometry<f64>>, MalformedGeometry> {
+        parse_geometry(&blob(0, &wkb.bytes))
+    }
+
+    /// The well-known binary of a point
+    fn point_wkb(little_endian: bool, x: f64, y: f64) -> Vec<u8> {
+        let mut wkb = Writer::new(little_endian);
+        wkb.header(1).position(&[x, y]);
+        wkb.bytes
+    }
+
+    /// A polygon of a square with a square hole
+    fn square_with_hole(little_endian: bool) -> Writer {
+        let mut wkb = Writer::new(little_endian);
+        wkb.header(3)
+            .u32(2)
+            .positions(&[
+                &[0.0, 0.0],
+                &[4.0, 0.0],
+                &[4.0, 4.0],
+                &[0.0, 4.0],
+                &[0.0, 0.0],
+            ])
+            .positions(&[&[1.0, 1.0], &[2.0, 1.0], &[2.0, 2.0], &[1.0, 1.0]]);
+        wkb
+    }
+
+    #[test]
+    fn reads_points_in_both_byte_orders() {
+        for little_endian in [true, false] {
+            let mut wkb = Writer::new(little_endian);
+            wkb.header(1).position(&[1.5, -2.0]);
+            assert_eq!(
+                parse(&wkb),
+                Ok(Some(Geometry::Point(point!(x: 1.5, y: -2.0))))
+            );
+        }
+    }
+
+    #[test]
+    fn reads_line_strings_and_polygons_with_holes() {
+        for little_endian in [true, false] {
+            let mut wkb = Writer::new(little_endian);
+            wkb.header(2).positions(&[&[0.0, 0.0], &[1.0, 2.0]]);
+            assert_eq!(
+                parse(&wkb),
+                Ok(Some(Geometry::LineString(
+                    line_string![(x: 0.0, y: 0.0), (x: 1.0, y: 2.0)]
+                )))
+            );
+
+            let expected = polygon!(
+                exterior: [(x: 0.0, y: 0.0), (x: 4.0, y: 0.0), (x: 4.0, y: 4.0), (x: 0.0, y: 4.0)],
+                interiors: [[(x: 1.0, y: 1.0), (x: 2.0, y: 1.0), (x: 2.0, y: 2.0)]],
+            );
+            assert_eq!(
+                parse(&square_with_hole(little_endian)),
+                Ok(Some(Geometry::Polygon(expected)))
+            );
+        }
+    }
+
+    #[test]
+    fn reads_multi_geometries_and_collections_of_mixed_byte_orders() {
+        let mut multi_point = Writer::new(true);
+        multi_point
+            .header(4)
+            .u32(2)
+            .bytes(&point_wkb(false, 1.0, 2.0))
+            .bytes(&point_wkb(true, 3.0, 4.0));
+        assert_eq!(
+            parse(&multi_point),
+            Ok(Some(Geometry::MultiPoint(MultiPoint::new(vec![
+                point!(x: 1.0, y: 2.0),
+                point!(x: 3.0, y: 4.0),
+            ]))))
+        );
+
+        let mut line = Writer::new(false);
+        line.header(2).positions(&[&[0.0, 0.0], &[1.0, 1.0]]);
+        let mut multi_line = Writer::new(true);
+        multi_line.header(5).u32(1).bytes(&line.bytes);
+        assert_eq!(
+            parse(&multi_line),
+            Ok(Some(Geometry::MultiLineString(MultiLineString::new(vec![
+                line_string![(x: 0.0, y: 0.0), (x: 1.0, y: 1.0)]
+            ]))))
+        );
+
+        let mut multi_polygon = Writer::new(false);
+        multi_polygon
+            .header(6)
+            .u32(2)
+            .bytes(&square_with_hole(true).bytes)
+            .bytes(&square_with_hole(false).bytes);
+        let Ok(Some(Geometry::MultiPolygon(polygons))) = parse(&multi_polygon) else {
+            panic!("Expected a multipolygon");
+        };
+        assert_eq!(polygons.0.len(), 2);
+        assert!(
+            polygons
+                .iter()
+                .all(|polygon| polygon.interiors().len() == 1)
+        );
+
+        let mut collection = Writer::new(true);
+        collection
+            .header(7)
+            .u32(2)
+            .bytes(&point_wkb(false, 5.0, 6.0))
+            .bytes(&line.bytes);
+        assert_eq!(
+            parse(&collection),
+            Ok(Some(Geometry::GeometryCollection(
+                GeometryCollection::new_from(vec![
+                    Geometry::Point(point!(x: 5.0, y: 6.0)),
+                    Geometry::LineString(line_string![(x: 0.0, y: 0.0), (x: 1.0, y: 1.0)]),
+                ])
+            )))
+        );
+    }
+
+    #[test]
+    fn drops_z_and_m_values() {
+        let expected = Ok(Some(Geometry::LineString(
+            line_string![(x: 1.0, y: 2.0), (x: 3.0, y: 4.0)],
+        )));
+        for little_endian in [true, false] {
+            let mut z = Writer::new(little_endian);
+            z.header(1002)
+                .positions(&[&[1.0, 2.0, 10.0], &[3.0, 4.0, 20.0]]);
+            assert_eq!(parse(&z), expected);
+
+            let mut m = Writer::new(little_endian);
+            m.header(2002)
+                .positions(&[&[1.0, 2.0, 0.5], &[3.0, 4.0, 0.7]]);
+            assert_eq!(parse(&m), expected);
+
+            let mut zm = Writer::new(little_endian);
+            zm.header(3002)
+                .positions(&[&[1.0, 2.0, 10.0, 0.5], &[3.0, 4.0, 20.0, 0.7]]);
+            assert_eq!(parse(&zm), expected);
+        }
+    }
+
+    #[test]
+    fn skips_envelopes_of_every_size() {
+        let wkb = point_wkb(true, 1.0, 2.0);
+        for envelope_code in 0..=4 {
+            assert_eq!(
+                parse_geometry(&blob(envelope_code, &wkb)),
+                Ok(Some(Geometry::Point(point!(x: 1.0, y: 2.0)))),
+                "envelope code {envelope_code}"
+            );
+        }
+        for envelope_code in 5..=7 {
+            assert_eq!(
+                parse_geometry(&blob(envelope_code, &wkb)),
+                Err(MalformedGeometry)
+            );
+        }
+    }
+
+    #[test]
+    fn reads_empty_geometries_as_none() {
+        let mut empty = blob(0, &[]);
+        empty[3] |= 0b1_0000;
+        assert_eq!(parse_geometry(&empty), Ok(None));
+    }
+
+    #[test]
+    fn rejects_malformed_and_truncated_blobs() {
+        let full = blob(1, &square_with_hole(false).bytes);
+        for length in 0..full.len() {
+            assert_eq!(
+                parse_geometry(&full[..length]),
+                Err(MalformedGeometry),
+                "truncated to {length} bytes"
+            );
+        }
+
+        let mut not_a_geopackage = full.clone();
+        not_a_geopackage[0] = b'X';
+        assert_eq!(parse_geometry(&not_a_geopackage), Err(MalformedGeometry));
+
+        let mut byte_order = point_wkb(true, 1.0, 2.0);
+        byte_order[0] = 2;
+        assert_eq!(
+            parse_geometry(&blob(0, &byte_order)),
+            Err(MalformedGeometry)
+        );
+
+        let mut unknown_type = Writer::new(true);
+        unknown_type.header(17);
+        assert_eq!(parse(&unknown_type), Err(MalformedGeometry));
+
+        let mut line = Writer::new(true);
+        line.header(2).positions(&[&[0.0, 0.0], &[1.0, 1.0]]);
+        let mut wrong_member = Writer::new(true);
+        wrong_member.header(4).u32(1).bytes(&line.bytes);
+        assert_eq!(parse(&wrong_member), Err(MalformedGeometry));
+    }
+}
@@ -0,0 +1,362 @@
+//! This module provides functionality for drawing GPX files as overlays.
+//!
+//! Tracks and routes become lines and waypoints become labeled points, projected
+//! like all other overlays. Tracks may be colored by the elevation or time of their points,
+//! splitting them into runs of points of the same color.
+
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use geo::{Coord, Geometry, LineString, Point};
+use roxmltree::{Document, Node};
+use shapefile::dbase::{FieldValue, Record};
+
+use super::overlay::{Overlay, ZOrder};
+use super::styles::{GpxStyle, LayerStyle, TrackColoring};
+
+/// A point of a track or route
+struct TrackPoint {
+    coord: Coord<f64>,
+    elevation: Option<f64>,
+    /// Seconds since 1970-01-01 UTC
+    time: Option<f64>,
+}
+
+/// A track segment or route with the name of its track or route
+struct Track {
+    name: Option<String>,
+    points: Vec<TrackPoint>,
+}
+
+/// Reads a GPX file as overlays drawn above all layers
+///
+/// The tracks and routes come first, as one overlay or, when colored, as one overlay per color,
+/// followed by the waypoints. The features have their "name" as attribute,
+/// the waypoints their elevation as "ele" too.
+///
+/// # Panics
+/// Panics if the file cannot be read or is no valid XML.
+#[must_use]
+pub fn gpx_overlays<'a>(path: &Path, gpx_style: &GpxStyle<'a>) -> Vec<Overlay<'a>> {
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|_| panic!("Error loading GPX: {}", path.display()));
+    let document = Document::parse(&text)
+        .unwrap_or_else(|error| panic!("Error parsing GPX {}: {error}", path.display()));
+
+    let mut tracks = Vec::new();
+    let mut waypoints = Vec::new();
+    for node in document.descendants().filter(Node::is_element) {
+        match node.tag_name().name() {
+            "trkseg" => tracks.push(Track {
+                name: node.parent().and_then(|track| child_text(track, "name")),
+                points: children(node, "trkpt").filter_map(track_point).collect(),
+            }),
+            "rte" => tracks.push(Track {
+                name: child_text(node, "name"),
+                points: children(node, "rtept").filter_map(track_point).collect(),
+            }),
+            "wpt" => waypoints.extend(waypoint(node)),
+            _ => {}
+        }
+    }
+    tracks.retain(|track| track.points.len() >= 2);
+
+    let mut overlays = match gpx_style.coloring {
+        Some(coloring) if !gpx_style.colors.is_empty() => {
+            colored_tracks(&tracks, coloring, gpx_style)
+        }
+        _ => vec![track_overlay(
+            tracks
+                .iter()
+                .map(|track| (line(&track.points), track.name.as_deref())),
+            gpx_style.track_style.clone(),
+        )],
+    };
+    overlays.push(Overlay {
+        features: waypoints,
+        layer_style: gpx_style.waypoint_style.clone(),
+        z_order: ZOrder::AboveLayers,
+        skipped: 0,
+    });
+    overlays.retain(|overlay| !overlay.features.is_empty());
+    overlays
+}
+
+/// Splits the tracks into runs of points in the same interval of the coloring value,
+/// one overlay per color
+///
+/// Tracks without any value are drawn in the stroke color of the track style.
+fn colored_tracks<'a>(
+    tracks: &[Track],
+    coloring: TrackColoring,
+    gpx_style: &GpxStyle<'a>,
+) -> Vec<Overlay<'a>> {
+    let value = |point: &TrackPoint| match coloring {
+        TrackColoring::Elevation => point.elevation,
+        TrackColoring::Time => point.time,
+    };
+    let values = tracks
+        .iter()
+        .flat_map(|track| &track.points)
+        .filter_map(value);
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+        (min.min(value), max.max(value))
+    });
+    let colors = gpx_style.colors;
+    let class = |value: f64| {
+        let position = if max > min {
+            (value - min) / (max - min)
+        } else {
+            0.0
+        };
+        interval(position, colors.len())
+    };
+
+    let mut runs: Vec<Vec<(LineString<f64>, Option<&str>)>> =
+        colors.iter().map(|_| Vec::new()).collect();
+    let mut uncolored = Vec::new();
+    for track in tracks {
+        let classes: Vec<Option<usize>> = track
+            .points
+            .iter()
+            .map(|point| value(point).map(class))
+            .collect();
+        let track_runs = class_runs(&classes);
+        if track_runs.is_empty() {
+            uncolored.push((line(&track.points), track.name.as_deref()));
+        }
+        for (class, points) in track_runs {
+            runs[class].push((line(&track.points[points]), track.name.as_deref()));
+        }
+    }
+
+    let mut overlays = vec![track_overlay(uncolored, gpx_style.track_style.clone())];
+    for (color, lines) in colors.iter().zip(runs) {
+        let layer_style = LayerStyle {
+            stroke: color,
+            ..gpx_style.track_style.clone()
+        };
+        overlays.push(track_overlay(lines, layer_style));
+    }
+    overlays
+}
+
+/// Splits the points of a track into runs of the same class, each with the range of its points
+///
+/// Points without a class take the class of the point before them, leading ones that of the first class.
+/// A run ends at the first point of the next run, so the colored lines join without gaps,
+/// and a last run of a single point is left out.
+/// Returns no runs if no point has a class.
+fn class_runs(classes: &[Option<usize>]) -> Vec<(usize, RangeInclusive<usize>)> {
+    let Some(mut current) = classes.iter().find_map(|class| *class) else {
+        return Vec::new();
+    };
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for (index, class) in classes.iter().enumerate() {
+        let class = class.unwrap_or(current);
+        if class != current {
+            runs.push((current, start..=index));
+            current = class;
+            start = index;
+        }
+    }
+    if start + 1 < classes.len() {
+        runs.push((current, start..=classes.len() - 1));
+    }
+    runs
+}
+
+/// Returns the index of the interval of a position from 0 to 1 among equal intervals
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn interval(position: f64, count: usize) -> usize {
+    ((position * count as f64) as usize).min(count - 1)
+}
+
+/// Returns an overlay of lines with their names
+fn track_overlay<'a, 'n>(
+    lines: impl IntoIterator<Item = (LineString<f64>, Option<&'n str>)>,
+    layer_style: LayerStyle<'a>,
+) -> Overlay<'a> {
+    Overlay {
+        features: lines
+            .into_iter()
+            .map(|(line, name)| (Geometry::LineString(line), named_record(name)))
+            .collect(),
+        layer_style,
+        z_order: ZOrder::AboveLayers,
+        skipped: 0,
+    }
+}
+
+/// Returns a waypoint with its name and elevation, `None` if it has no valid position
+fn waypoint(node: Node) -> Option<(Geometry<f64>, Record)> {
+    let point = track_point(node)?;
+    let mut record = named_record(child_text(node, "name").as_deref());
+    if let Some(elevation) = point.elevation {
+        record.insert("ele".to_string(), FieldValue::Numeric(Some(elevation)));
+    }
+    Some((Geometry::Point(Point(point.coord)), record))
+}
+
+/// Returns a record with a "name" field if there is a name
+fn named_record(name: Option<&str>) -> Record {
+    let mut record = Record::default();
+    if let Some(name) = name {
+        record.insert(
+            "name".to_string(),
+            FieldValue::Character(Some(name.to_string())),
+        );
+    }
+    record
+}
+
+/// Returns the line through the points
+fn line(points: &[TrackPoint]) -> LineString<f64> {
+    points.iter().map(|point| point.coord).collect()
+}
+
+/// Reads the position, elevation and time of a point, `None` if it has no valid position
+fn track_point(node: Node) -> Option<TrackPoint> {
+    let lat: f64 = node.attribute("lat")?.trim().parse().ok()?;
+    let lon: f64 = node.attribute("lon")?.trim().parse().ok()?;
+    if !lat.is_finite() || !lon.is_finite() {
+        return None;
+    }
+    Some(TrackPoint {
+        coord: Coord { x: lon, y: lat },
+        elevation: child_text(node, "ele").and_then(|text| text.trim().parse().ok()),
+        time: child_text(node, "time").and_then(|text| timestamp(&text)),
+    })
+}
+
+/// Returns the child elements with a name
+fn children<'d, 'i>(node: Node<'d, 'i>, name: &'static str) -> impl Iterator<Item = Node<'d, 'i>> {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+/// Returns the text of the first child element with a name, `None` if it is missing or empty
+fn child_text(node: Node, name: &'static str) -> Option<String> {
+    let text = children(node, name).next()?.text()?.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Parses an ISO 8601 date and time, e.g. "2024-05-31T12:30:00Z", to seconds since 1970-01-01 UTC
+///
+/// Times without a zone are taken as UTC. Zone offsets may be given as "+05:30", "+0530" or "+05",
+/// any other offset makes the time invalid.
+#[allow(clippy::cast_precision_loss)]
+fn timestamp(text: &str) -> Option<f64> {
+    let (date, time) = text.trim().split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+
+    let (clock, zone) = time.split_at(time.find(['Z', '+', '-']).unwrap_or(time.len()));
+    let mut clock = clock.splitn(3, ':');
+    let hours: f64 = clock.next()?.parse().ok()?;
+    let minutes: f64 = clock.next()?.parse().ok()?;
+    let seconds: f64 = clock
+        .next()
+        .map_or(Some(0.0), |seconds| seconds.parse().ok())?;
+    let offset = match zone.split_at_checked(1) {
+        Some(("+", offset)) => offset_seconds(offset)?,
+        Some(("-", offset)) => -offset_seconds(offset)?,
+        _ => 0.0,
+    };
+
+    let days = days_from_civil(year, month, day);
+    Some(days as f64 * 86_400.0 + hours * 3600.0 + minutes * 60.0 + seconds - offset)
+}
+
+/// Parses a zone offset without its sign, as "HH:MM", "HHMM" or "HH", to seconds
+fn offset_seconds(offset: &str) -> Option<f64> {
+    let (hours, minutes) = match (offset.len(), offset.split_once(':')) {
+        (5, Some(parts)) => parts,
+        (4, None) => offset.split_at_checked(2)?,
+        (2, None) => (offset, "00"),
+        _ => return None,
+    };
+    let two_digits = |text: &str| {
+        (text.len() == 2 && text.bytes().all(|byte| byte.is_ascii_digit()))
+            .then(|| text.parse::<f64>().ok())
+            .flatten()
+    };
+    Some(two_digits(hours)? * 3600.0 + two_digits(minutes)? * 60.0)
+}
+
+/// Returns the days since 1970-01-01 of a date of the proleptic Gregorian calendar
+///
+/// Years are counted from March in eras of 400 years, so the leap day ends a year.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_days_from_civil_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2000, 2, 29), 11_016);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(2024, 5, 31), 19_874);
+        assert_eq!(days_from_civil(1600, 1, 1), -135_140);
+    }
+
+    #[test]
+    fn parses_timestamps_with_zone_offsets() {
+        let utc = timestamp("2024-05-31T12:30:00Z");
+        assert_eq!(utc, Some(19_874.0 * 86_400.0 + 45_000.0));
+        assert_eq!(timestamp("2024-05-31T12:30:00"), utc);
+        assert_eq!(timestamp("2024-05-31T18:00:00+05:30"), utc);
+        assert_eq!(timestamp("2024-05-31T18:00:00+0530"), utc);
+        assert_eq!(timestamp("2024-05-31T10:30:00-02"), utc);
+        assert_eq!(
+            timestamp("2024-05-31T12:30:00.5Z"),
+            utc.map(|time| time + 0.5)
+        );
+    }
+
+    #[test]
+    fn rejects_unrecognized_zone_offsets() {
+        assert_eq!(timestamp("2024-05-31T18:00:00+053"), None);
+        assert_eq!(timestamp("2024-05-31T18:00:00+5:30"), None);
+        assert_eq!(timestamp("2024-05-31T18:00:00+05:3"), None);
+        assert_eq!(timestamp("2024-05-31T18:00:00+05305"), None);
+        assert_eq!(timestamp("2024-05-31T18:00:00+"), None);
+    }
+
+    #[test]
+    fn fills_points_without_a_class_from_the_points_before() {
+        let runs = class_runs(&[None, Some(1), None, Some(2), None]);
+        assert_eq!(runs, vec![(1, 0..=3), (2, 3..=4)]);
+    }
+
+    #[test]
+    fn keeps_a_single_class_as_one_run() {
+        assert_eq!(class_runs(&[Some(0); 4]), vec![(0, 0..=3)]);
+    }
+
+    #[test]
+    fn splits_runs_at_every_class_change() {
+        let runs = class_runs(&[Some(0), Some(1), Some(0), Some(1)]);
+        assert_eq!(runs, vec![(0, 0..=1), (1, 1..=2), (0, 2..=3)]);
+    }
+
+    #[test]
+    fn has_no_runs_without_classes() {
+        assert!(class_runs(&[None, None]).is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+//! This module provides functionality for drawing the graticule grid, special parallels and meridians, and degree labels.
+
+use svg::{Node, node::element};
+
+use super::Map;
+use super::draw_svg::{draw_label, draw_polyline};
+use super::labels::{DIGIT_HEIGHT, text_width};
+use super::styles::{Graticule, LabelStyle, LayerStyle, ReferenceLine, ReferenceLines};
+use super::utils::mapping_function;
+
+/// Latitude of the tropics in degrees, the tilt of the Earth's axis
+pub const TROPIC_LATITUDE: f64 = 23.436;
+/// Latitude of the polar circles in degrees
+pub const POLAR_CIRCLE_LATITUDE: f64 = 90.0 - TROPIC_LATITUDE;
+
+/// Distance in pixels between a degree label and the map edge
+const LABEL_PADDING: f64 = 3.0;
+/// Tolerance in degrees when comparing multiples of an interval
+const TOLERANCE: f64 = 1e-9;
+
+/// Draws the graticule grid (latitude and longitude lines)
+///
+/// Minor lines are drawn first, leaving out those that coincide with a major line.
+/// The major meridians are labeled at the top and bottom edge, the major parallels
+/// at the left and right edge, if the graticule has labels.
+///
+/// # Arguments
+/// * `map` - The map dimensions and projection settings
+/// * `document` - The SVG document or group to modify
+/// * `graticule` - The spacing, extent and labels of the grid
+/// * `graticule_style` - The style to use for the major lines
+pub fn draw_graticules(
+    map: &Map,
+    document: &mut impl Node,
+    graticule: &Graticule,
+    graticule_style: &LayerStyle,
+) {
+    let (lon_range, lat_range) = if graticule.clip_to_extent {
+        ((map.lon_min, map.lon_max), (map.lat_min, map.lat_max))
+    } else {
+        ((-180.0, 180.0), (-90.0, 90.0))
+    };
+
+    if let Some(interval) = graticule.minor_lon_interval {
+        for lon in multiples(lon_range, interval) {
+            if !is_multiple(lon, graticule.lon_interval) {
+                draw_meridian(map, document, lon, lat_range, &graticule.minor_style);
+            }
+        }
+    }
+    if let Some(interval) = graticule.minor_lat_interval {
+        for lat in multiples(lat_range, interval) {
+            if !is_multiple(lat, graticule.lat_interval) {
+                draw_parallel(map, document, lat, lon_range, &graticule.minor_style);
+            }
+        }
+    }
+
+    for lon in multiples(lon_range, graticule.lon_interval) {
+        draw_meridian(map, document, lon, lat_range, graticule_style);
+    }
+    for lat in multiples(lat_range, graticule.lat_interval) {
+        draw_parallel(map, document, lat, lon_range, graticule_style);
+    }
+
+    if let Some(label_style) = &graticule.labels {
+        let fill = label_style.fill.unwrap_or(graticule_style.stroke);
+        draw_edge_labels(map, document, graticule, label_style, fill);
+    }
+}
+
+/// Draws the equator line
+///
+/// # Arguments
+/// * `map` - The map dimensions and projection settings
+/// * `document` - The SVG document or group to modify
+/// * `equator_style` - The style to use for the equator line
+pub fn draw_equator(map: &Map, document: &mut impl Node, equator_style: &LayerStyle) {
+    draw_parallel(
+        map,
+        document,
+        0.0,
+        (map.lon_min, map.lon_max),
+        equator_style,
+    );
+}
+
+/// Draws the tropics, polar circles, prime meridian, antimeridian and custom lines that have a style
+///
+/// Lines outside the map extent are left out.
+///
+/// # Arguments
+/// * `map` - The map dimensions and projection settings
+/// * `document` - The SVG document or group to modify
+/// * `reference_lines` - The lines to draw with their styles
+pub fn draw_reference_lines(map: &Map, document: &mut impl Node, reference_lines: &ReferenceLines) {
+    let parallels = [
+        (TROPIC_LATITUDE, &reference_lines.tropic_of_cancer),
+        (-TROPIC_LATITUDE, &reference_lines.tropic_of_capricorn),
+        (POLAR_CIRCLE_LATITUDE, &reference_lines.arctic_circle),
+        (-POLAR_CIRCLE_LATITUDE, &reference_lines.antarctic_circle),
+    ];
+    for (lat, layer_style) in parallels {
+        if let Some(layer_style) = layer_style {
+            draw_reference_parallel(map, document, lat, layer_style);
+        }
+    }
+
+    let meridians = [
+        (0.0, &reference_lines.prime_meridian),
+        (-180.0, &reference_lines.antimeridian),
+        (180.0, &reference_lines.antimeridian),
+    ];
+    for (lon, layer_style) in meridians {
+        if let Some(layer_style) = layer_style {
+            draw_reference_meridian(map, document, lon, layer_style);
+        }
+    }
+
+    for line in &reference_lines.custom {
+        match line {
+            ReferenceLine::Parallel { lat, layer_style } => {
+                draw_reference_parallel(map, document, *lat, layer_style);
+            }
+            ReferenceLine::Meridian { lon, layer_style } => {
+                draw_reference_meridian(map, document, *lon, layer_style);
+            }
+        }
+    }
+}
+
+/// Draws a meridian between two latitudes
+pub fn draw_meridian(
+    map: &Map,
+    document: &mut impl Node,
+    lon: f64,
+    (lat_min, lat_max): (f64, f64),
+    layer_style: &LayerStyle,
+) {
+    let points: Vec<(f64, f64)> = samples(lat_min, lat_max)
+        .into_iter()
+        .map(|lat| mapping_function(lon, lat, map))
+        .collect();
+    draw_polyline(&points, document, layer_style, element::path::Data::new());
+}
+
+/// Draws a parallel between two longitudes
+pub fn draw_parallel(
+    map: &Map,
+    document: &mut impl Node,
+    lat: f64,
+    (lon_min, lon_max): (f64, f64),
+    layer_style: &LayerStyle,
+) {
+    let points: Vec<(f64, f64)> = samples(lon_min, lon_max)
+        .into_iter()
+        .map(|lon| mapping_function(lon, lat, map))
+        .collect();
+    draw_polyline(&points, document, layer_style, element::path::Data::new());
+}
+
+/// Formats a latitude as degrees with its hemisphere, e.g. "30°N"
+#[must_use]
+pub fn format_lat(lat: f64) -> String {
+    format_degrees(lat, 'N', 'S')
+}
+
+/// Formats a longitude as degrees with its hemisphere, e.g. "60°W"
+///
+/// The prime meridian and the antimeridian have no hemisphere.
+#[must_use]
+pub fn format_lon(lon: f64) -> String {
+    format_degrees(lon, 'E', 'W')
+}
+
+/// Returns the multiples of an interval within a range, empty if the interval is not positive
+#[must_use]
+pub fn multiples((min, max): (f64, f64), interval: f64) -> Vec<f64> {
+    let mut values = Vec::new();
+    if interval <= 0.0 {
+        return values;
+    }
+    let mut k = (min / interval - TOLERANCE).ceil();
+    while k * interval <= max + TOLERANCE {
+        values.push(k * interval);
+        k += 1.0;
+    }
+    values
+}
+
+/// Draws a parallel across the map, if it lies within the map extent
+fn draw_reference_parallel(
+    map: &Map,
+    document: &mut impl Node,
+    lat: f64,
+    layer_style: &LayerStyle,
+) {
+    if (map.lat_min..=map.lat_max).contains(&lat) {
+        draw_parallel(map, document, lat, (map.lon_min, map.lon_max), layer_style);
+    }
+}
+
+/// Draws a meridian across the map, if it lies within the map extent
+fn draw_reference_meridian(
+    map: &Map,
+    document: &mut impl Node,
+    lon: f64,
+    layer_style: &LayerStyle,
+) {
+    if (map.lon_min..=map.lon_max).contains(&lon) {
+        draw_meridian(map, document, lon, (map.lat_min, map.lat_max), layer_style);
+    }
+}
+
+/// Labels the major meridians along the top and bottom edge and the major parallels
+/// along the left and right edge
+///
+/// Labels that would stick out of the map or run into the labels of the other edges are left out.
+fn draw_edge_labels(
+    map: &Map,
+    document: &mut impl Node,
+    graticule: &Graticule,
+    label_style: &LabelStyle,
+    fill: &str,
+) {
+    let width = f64::from(map.cols);
+    let height = f64::from(map.rows);
+    let font_size = label_style.font_size;
+
+    let centered = LabelStyle {
+        text_anchor: "middle",
+        ..label_style.clone()
+    };
+    for lon in multiples((map.lon_min, map.lon_max), graticule.lon_interval) {
+        let text = format_lon(lon);
+        let (x, _) = mapping_function(lon, map.lat_max, map);
+        let half_width = text_width(&text, font_size) / 2.0;
+        if x - half_width < LABEL_PADDING || x + half_width > width - LABEL_PADDING {
+            continue;
+        }
+        let top = LABEL_PADDING + DIGIT_HEIGHT * font_size;
+        draw_label((x, top), &text, document, &centered, fill);
+        draw_label(
+            (x, height - LABEL_PADDING),
+            &text,
+            document,
+            &centered,
+            fill,
+        );
+    }
+
+    let left = LabelStyle {
+        text_anchor: "start",
+        ..label_style.clone()
+    };
+    let right = LabelStyle {
+        text_anchor: "end",
+        ..label_style.clone()
+    };
+    // Parallels keep clear of the bands of the meridian labels
+    let band = 2.0 * LABEL_PADDING + DIGIT_HEIGHT * font_size;
+    for lat in multiples((map.lat_min, map.lat_max), graticule.lat_interval) {
+        let text = format_lat(lat);
+        let (_, y) = mapping_function(map.lon_min, lat, map);
+        if y - font_size / 2.0 < band || y + font_size / 2.0 > height - band {
+            continue;
+        }
+        let baseline = y + DIGIT_HEIGHT * font_size / 2.0;
+        draw_label((LABEL_PADDING, baseline), &text, document, &left, fill);
+        draw_label(
+            (width - LABEL_PADDING, baseline),
+            &text,
+            document,
+            &right,
+            fill,
+        );
+    }
+}
+
+/// Formats degrees with the letter of their hemisphere, leaving it out for 0° and 180°
+fn format_degrees(value: f64, positive: char, negative: char) -> String {
+    let rounded = (value * 1e6).round() / 1e6;
+    let magnitude = rounded.abs();
+    if magnitude < TOLERANCE || (magnitude - 180.0).abs() < TOLERANCE {
+        format!("{magnitude}°")
+    } else if rounded > 0.0 {
+        format!("{magnitude}°{positive}")
+    } else {
+        format!("{magnitude}°{negative}")
+    }
+}
+
+/// Returns whether a value is a multiple of an interval
+fn is_multiple(value: f64, interval: f64) -> bool {
+    interval > 0.0 && ((value / interval).round() * interval - value).abs() < TOLERANCE
+}
+
+/// Returns the values from `min` to `max` about one degree apart, including both ends
+fn samples(min: f64, max: f64) -> Vec<f64> {
+    let steps = (max - min).abs().ceil().max(1.0);
+    let mut values = Vec::new();
+    let mut step = 0.0;
+    while step <= steps {
+        values.push(min + (max - min) * step / steps);
+        step += 1.0;
+    }
+    values
+}
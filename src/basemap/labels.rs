@@ -0,0 +1,569 @@
+//! Label placement
+//!
+//! Labels are collected from all layers and placed once the layers are drawn, most
+//! important first. Each label tries a few candidate positions, around its anchor or along
+//! its line, and is dropped if all of them collide with a label placed before it.
+
+use std::f64::consts::PI;
+
+use geo::{ChaikinSmoothing, LineString};
+use svg::{Node, node::element};
+
+use super::draw_svg::{draw_label, draw_line_label};
+use super::styles::LabelStyle;
+
+/// Approximate width of a character relative to the font size
+pub const CHAR_WIDTH: f64 = 0.6;
+/// Height of a digit relative to the font size
+pub const DIGIT_HEIGHT: f64 = 0.7;
+/// Offset from the vertical center of a label to its baseline, relative to the font size
+pub const BASELINE: f64 = 0.35;
+/// Gap between an anchor and a label placed beside it, relative to the font size
+const GAP: f64 = 0.3;
+/// The sharpest turn in degrees a line may take at a vertex under a label
+const MAX_BEND: f64 = 25.0;
+/// The most a line may turn in degrees in total under a label
+const MAX_TOTAL_BEND: f64 = 60.0;
+
+/// A label waiting to be placed
+pub struct Label<'a> {
+    /// The text of the label
+    pub text: String,
+    /// The anchor of the label in pixel coordinates
+    pub anchor: (f64, f64),
+    /// The priority of the label, lower values are placed first
+    /// (e.g. the `scalerank` of a Natural Earth feature)
+    pub priority: f64,
+    /// The style of the label
+    pub style: LabelStyle<'a>,
+    /// The text color
+    /// (e.g. "black", "#000000")
+    pub fill: &'a str,
+    /// Whether the label may move beside its anchor if its preferred position is taken
+    pub movable: bool,
+}
+
+/// An axis-aligned box in pixel coordinates
+#[derive(Clone, Copy)]
+struct BoundingBox {
+    min_x: f64,
+    min_y: f64,
+    max_x: f64,
+    max_y: f64,
+}
+
+impl BoundingBox {
+    /// Creates a box of the given size around a center
+    fn around(center: (f64, f64), width: f64, height: f64) -> Self {
+        BoundingBox {
+            min_x: center.0 - width / 2.0,
+            min_y: center.1 - height / 2.0,
+            max_x: center.0 + width / 2.0,
+            max_y: center.1 + height / 2.0,
+        }
+    }
+
+    /// Whether the two boxes overlap
+    fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min_x < other.max_x
+            && other.min_x < self.max_x
+            && self.min_y < other.max_y
+            && other.min_y < self.max_y
+    }
+}
+
+/// A label following a line feature
+pub struct LineLabel<'a> {
+    /// The text of the label
+    pub text: String,
+    /// The line the label follows in pixel coordinates
+    pub points: Vec<(f64, f64)>,
+    /// The priority of the label, lower values are placed first
+    pub priority: f64,
+    /// The style of the label, whose line spacing sets the distance between repeated labels
+    pub style: LabelStyle<'a>,
+    /// The text color
+    /// (e.g. "black", "#000000")
+    pub fill: &'a str,
+}
+
+/// A label of either kind waiting to be placed
+enum Pending<'a> {
+    Point(Label<'a>),
+    Line(LineLabel<'a>),
+}
+
+impl<'a> Pending<'a> {
+    fn priority(&self) -> f64 {
+        match self {
+            Pending::Point(label) => label.priority,
+            Pending::Line(label) => label.priority,
+        }
+    }
+
+    fn style(&self) -> &LabelStyle<'a> {
+        match self {
+            Pending::Point(label) => &label.style,
+            Pending::Line(label) => &label.style,
+        }
+    }
+}
+
+/// Places labels so that they do not overlap
+#[derive(Default)]
+pub struct LabelPlacer<'a> {
+    /// The labels waiting to be placed
+    labels: Vec<Pending<'a>>,
+    /// The boxes of the labels placed so far
+    placed: Vec<BoundingBox>,
+    /// The number of paths defined for line labels so far, used for their ids
+    line_paths: usize,
+}
+
+impl<'a> LabelPlacer<'a> {
+    /// Creates a placer without any labels
+    #[must_use]
+    pub fn new() -> Self {
+        LabelPlacer::default()
+    }
+
+    /// Keeps labels clear of an area, such as a point marker
+    ///
+    /// # Arguments
+    /// * `center` - The center of the area in pixel coordinates
+    /// * `width` - The width of the area in pixels
+    /// * `height` - The height of the area in pixels
+    pub fn reserve(&mut self, center: (f64, f64), width: f64, height: f64) {
+        self.placed.push(BoundingBox::around(center, width, height));
+    }
+
+    /// Adds a label to be placed at a point
+    pub fn add(&mut self, label: Label<'a>) {
+        self.labels.push(Pending::Point(label));
+    }
+
+    /// Adds a label to be placed along a line
+    pub fn add_line(&mut self, label: LineLabel<'a>) {
+        self.labels.push(Pending::Line(label));
+    }
+
+    /// Places and draws the collected labels
+    ///
+    /// Labels are placed in order of priority. Each point label is placed where its style asks if
+    /// possible, otherwise, if movable, centered on its anchor or above, below, right, left or
+    /// diagonally beside it.
+    /// Line labels are repeated along their smoothed line, skipping stretches with sharp bends.
+    /// Labels that collide with an already placed label in every position are dropped,
+    /// as are labels of a font size that is not positive.
+    ///
+    /// # Arguments
+    /// * `document` - The SVG document or group to modify
+    pub fn draw(&mut self, document: &mut impl Node) {
+        let mut labels = std::mem::take(&mut self.labels);
+        labels.sort_by(|a, b| a.priority().total_cmp(&b.priority()));
+        // Text without a positive size is invisible and has no extent to keep clear
+        labels.retain(|label| label.style().font_size > 0.0);
+
+        for label in labels {
+            match label {
+                Pending::Point(label) => self.place_point_label(&label, document),
+                Pending::Line(label) => self.place_line_label(&label, document),
+            }
+        }
+    }
+
+    /// Places a label at the first free candidate position around its anchor
+    fn place_point_label(&mut self, label: &Label, document: &mut impl Node) {
+        let style = &label.style;
+        let text = transform_text(&label.text, style);
+        let (width, height) = text_extent(&text, style);
+        let gap = style.font_size * GAP;
+        let alternatives = if label.movable {
+            candidates(label.anchor, width, height, gap).to_vec()
+        } else {
+            Vec::new()
+        };
+        let placement = std::iter::once(preferred_center(label.anchor, width, style))
+            .chain(alternatives)
+            .map(|center| (center, BoundingBox::around(center, width, height)))
+            .find(|(_, bbox)| self.is_free(std::slice::from_ref(bbox)));
+
+        if let Some((center, bbox)) = placement {
+            self.placed.push(bbox);
+            // The text is drawn with its own anchor, so shift from the box center to it
+            let x = match style.text_anchor {
+                "start" => bbox.min_x,
+                "end" => bbox.max_x,
+                _ => center.0,
+            };
+            let position = (x, center.1 + style.font_size * BASELINE);
+            draw_label(position, &text, document, style, label.fill);
+        }
+    }
+
+    /// Places a label repeatedly along a line, once per spacing interval
+    fn place_line_label(&mut self, label: &LineLabel, document: &mut impl Node) {
+        let style = &label.style;
+        let Some(spacing) = style.line_spacing else {
+            return;
+        };
+        let text = transform_text(&label.text, style);
+        let (width, height) = text_extent(&text, style);
+
+        let line = smooth(&label.points);
+        let lengths = cumulative_lengths(&line);
+        let total = lengths.last().copied().unwrap_or(0.0);
+        if line.len() < 2 || text.is_empty() || total < width {
+            return;
+        }
+
+        // One candidate in the middle of each spacing interval along the line,
+        // a single one for a spacing that is not a positive number
+        let count = if spacing.is_finite() && spacing > 0.0 {
+            (total / spacing).floor().max(1.0)
+        } else {
+            1.0
+        };
+        let mut index = 0.0;
+        while index < count {
+            let center = (index + 0.5) * total / count;
+            index += 1.0;
+
+            let (start, end) = (center - width / 2.0, center + width / 2.0);
+            if start < 0.0 || end > total {
+                continue;
+            }
+            let window = sub_line(&line, &lengths, start, end);
+            if has_sharp_bend(&window) {
+                continue;
+            }
+            let boxes = boxes_along(&line, &lengths, start, end, height);
+            if !self.is_free(&boxes) {
+                continue;
+            }
+            self.placed.extend(boxes);
+
+            // Text follows the direction of its path, so keep it running left to right
+            let window = match (window.first(), window.last()) {
+                (Some(first), Some(last)) if last.0 < first.0 => window.into_iter().rev().collect(),
+                _ => window,
+            };
+            let path_id = format!("label-path-{}", self.line_paths);
+            self.line_paths += 1;
+            document.append(element::Definitions::new().add(line_path(&path_id, &window)));
+            draw_line_label(&path_id, &text, document, style, label.fill);
+        }
+    }
+
+    /// Whether none of the boxes collide with an already placed label
+    fn is_free(&self, boxes: &[BoundingBox]) -> bool {
+        boxes
+            .iter()
+            .all(|bbox| self.placed.iter().all(|placed| !placed.intersects(bbox)))
+    }
+}
+
+/// Applies the case transform of a label style to a text
+fn transform_text(text: &str, style: &LabelStyle) -> String {
+    if style.uppercase {
+        text.to_uppercase()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Estimates the width of a text in pixels without letter spacing
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn text_width(text: &str, font_size: f64) -> f64 {
+    text.chars().count() as f64 * CHAR_WIDTH * font_size
+}
+
+/// Estimates the width and height of a text in pixels
+#[allow(clippy::cast_precision_loss)]
+fn text_extent(text: &str, style: &LabelStyle) -> (f64, f64) {
+    let chars = text.chars().count() as f64;
+    (
+        text_width(text, style.font_size) + chars * style.letter_spacing,
+        style.font_size,
+    )
+}
+
+/// The center of a label placed as the style asks, by its text anchor and offset
+fn preferred_center(anchor: (f64, f64), width: f64, style: &LabelStyle) -> (f64, f64) {
+    let x = anchor.0 + style.offset.0;
+    let y = anchor.1 + style.offset.1;
+    match style.text_anchor {
+        "start" => (x + width / 2.0, y),
+        "end" => (x - width / 2.0, y),
+        _ => (x, y),
+    }
+}
+
+/// Candidate centers of a label around its anchor, in order of preference
+fn candidates(anchor: (f64, f64), width: f64, height: f64, gap: f64) -> [(f64, f64); 9] {
+    let (x, y) = anchor;
+    let dx = width / 2.0 + gap;
+    let dy = height / 2.0 + gap;
+    [
+        (x, y),
+        (x, y - dy),
+        (x, y + dy),
+        (x + dx, y),
+        (x - dx, y),
+        (x + dx, y - dy),
+        (x - dx, y - dy),
+        (x + dx, y + dy),
+        (x - dx, y + dy),
+    ]
+}
+
+/// Smooths a line with two rounds of Chaikin's corner cutting
+fn smooth(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    LineString::from(points.to_vec())
+        .chaikin_smoothing(2)
+        .coords()
+        .map(|coord| (coord.x, coord.y))
+        .collect()
+}
+
+/// The distance along the line to each of its vertices
+fn cumulative_lengths(line: &[(f64, f64)]) -> Vec<f64> {
+    let mut total = 0.0;
+    let mut lengths = Vec::with_capacity(line.len());
+    for (index, point) in line.iter().enumerate() {
+        if index > 0 {
+            let previous = line[index - 1];
+            total += (point.0 - previous.0).hypot(point.1 - previous.1);
+        }
+        lengths.push(total);
+    }
+    lengths
+}
+
+/// The point at a distance along the line
+fn point_at(line: &[(f64, f64)], lengths: &[f64], distance: f64) -> (f64, f64) {
+    let index = lengths
+        .partition_point(|length| *length < distance)
+        .clamp(1, line.len() - 1);
+    let (a, b) = (line[index - 1], line[index]);
+    let segment = lengths[index] - lengths[index - 1];
+    let t = if segment > 0.0 {
+        ((distance - lengths[index - 1]) / segment).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+/// The part of the line between two distances along it
+fn sub_line(line: &[(f64, f64)], lengths: &[f64], start: f64, end: f64) -> Vec<(f64, f64)> {
+    let mut points = vec![point_at(line, lengths, start)];
+    points.extend(
+        line.iter()
+            .zip(lengths)
+            .filter(|(_, length)| **length > start && **length < end)
+            .map(|(point, _)| *point),
+    );
+    points.push(point_at(line, lengths, end));
+    points
+}
+
+/// Whether the line turns too sharply at a vertex or too much overall to carry a label
+fn has_sharp_bend(line: &[(f64, f64)]) -> bool {
+    let directions: Vec<f64> = line
+        .windows(2)
+        .filter(|pair| pair[0] != pair[1])
+        .map(|pair| (pair[1].1 - pair[0].1).atan2(pair[1].0 - pair[0].0))
+        .collect();
+
+    let mut total_turn = 0.0;
+    for pair in directions.windows(2) {
+        let turn = (pair[1] - pair[0] + PI).rem_euclid(2.0 * PI) - PI;
+        if turn.abs() > MAX_BEND.to_radians() {
+            return true;
+        }
+        total_turn += turn.abs();
+    }
+    total_turn > MAX_TOTAL_BEND.to_radians()
+}
+
+/// Square boxes the size of the font along the part of the line carrying a label,
+/// none for a size that is not positive
+fn boxes_along(
+    line: &[(f64, f64)],
+    lengths: &[f64],
+    start: f64,
+    end: f64,
+    size: f64,
+) -> Vec<BoundingBox> {
+    let mut boxes = Vec::new();
+    if size.is_nan() || size <= 0.0 {
+        return boxes;
+    }
+    let mut distance = start + size / 2.0;
+    while distance < end {
+        boxes.push(BoundingBox::around(
+            point_at(line, lengths, distance),
+            size,
+            size,
+        ));
+        distance += size / 2.0;
+    }
+    boxes
+}
+
+/// Builds the path a line label follows
+fn line_path(id: &str, points: &[(f64, f64)]) -> element::Path {
+    let data = element::path::Data::new().move_to(points[0]);
+    let data = points
+        .iter()
+        .skip(1)
+        .fold(data, |data, point| data.line_to(*point));
+    element::Path::new().set("id", id).set("d", data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A label along a straight horizontal line
+    fn line_label(text: &str, font_size: f64) -> LineLabel<'static> {
+        LineLabel {
+            text: text.to_string(),
+            points: vec![(0.0, 50.0), (500.0, 50.0)],
+            priority: 0.0,
+            style: LabelStyle {
+                font_size,
+                line_spacing: Some(200.0),
+                ..LabelStyle::default()
+            },
+            fill: "black",
+        }
+    }
+
+    /// A label at a point
+    fn point_label(text: &str, anchor: (f64, f64), priority: f64, movable: bool) -> Label<'static> {
+        Label {
+            text: text.to_string(),
+            anchor,
+            priority,
+            style: LabelStyle::default(),
+            fill: "black",
+            movable,
+        }
+    }
+
+    /// Draws the labels of a placer and returns the SVG text
+    fn drawn(placer: &mut LabelPlacer) -> String {
+        let mut document = svg::Document::new();
+        placer.draw(&mut document);
+        document.to_string()
+    }
+
+    #[test]
+    fn places_the_label_of_lower_priority_first() {
+        let mut placer = LabelPlacer::new();
+        placer.add(point_label("Minor", (100.0, 100.0), 5.0, false));
+        placer.add(point_label("Major", (100.0, 100.0), 1.0, false));
+        let svg = drawn(&mut placer);
+        assert!(svg.contains("Major"));
+        assert!(!svg.contains("Minor"));
+    }
+
+    #[test]
+    fn moves_only_a_movable_label_beside_a_taken_position() {
+        // Just below the first label, so only the position further below is free
+        let below = (100.0, 100.0 + LabelStyle::default().font_size * 0.9);
+        for (movable, placed) in [(true, true), (false, false)] {
+            let mut placer = LabelPlacer::new();
+            placer.add(point_label("First", (100.0, 100.0), 1.0, false));
+            placer.add(point_label("Second", below, 2.0, movable));
+            let svg = drawn(&mut placer);
+            assert!(svg.contains("First"));
+            assert_eq!(svg.contains("Second"), placed);
+        }
+    }
+
+    #[test]
+    fn keeps_labels_clear_of_reserved_areas_and_apart_ones_free() {
+        let mut placer = LabelPlacer::new();
+        placer.reserve((100.0, 100.0), 400.0, 400.0);
+        placer.add(point_label("Covered", (100.0, 100.0), 1.0, true));
+        placer.add(point_label("Apart", (500.0, 500.0), 1.0, false));
+        let svg = drawn(&mut placer);
+        assert!(!svg.contains("Covered"));
+        assert!(svg.contains("Apart"));
+    }
+
+    #[test]
+    fn leaves_out_labels_without_a_positive_font_size() {
+        for font_size in [0.0, -12.0, f64::NAN] {
+            let mut placer = LabelPlacer::new();
+            placer.add_line(line_label("River", font_size));
+            let mut document = svg::Document::new();
+            placer.draw(&mut document);
+            assert!(!document.to_string().contains("River"));
+        }
+    }
+
+    #[test]
+    fn repeats_line_labels_once_per_spacing() {
+        let mut placer = LabelPlacer::new();
+        placer.add_line(line_label("River", 12.0));
+        let svg = drawn(&mut placer);
+        assert_eq!(svg.matches("<textPath").count(), 2);
+    }
+
+    #[test]
+    fn finds_sharp_and_gradual_bends() {
+        let straight = [(0.0, 0.0), (10.0, 0.0), (20.0, 0.0)];
+        let corner = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)];
+        let gentle = [(0.0, 0.0), (10.0, 0.0), (20.0, 2.0)];
+        let winding: Vec<(f64, f64)> = (0..8)
+            .map(|step| {
+                let angle = f64::from(step) * 20.0_f64.to_radians();
+                (10.0 * angle.sin(), 10.0 * (1.0 - angle.cos()))
+            })
+            .collect();
+        assert!(!has_sharp_bend(&straight));
+        assert!(has_sharp_bend(&corner));
+        assert!(!has_sharp_bend(&gentle));
+        assert!(has_sharp_bend(&winding));
+    }
+
+    #[test]
+    fn ignores_repeated_points_when_finding_bends() {
+        assert!(!has_sharp_bend(&[
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 0.0),
+            (20.0, 0.0)
+        ]));
+    }
+
+    #[test]
+    fn covers_the_label_with_overlapping_boxes() {
+        let line = [(0.0, 0.0), (100.0, 0.0)];
+        let lengths = cumulative_lengths(&line);
+        let boxes = boxes_along(&line, &lengths, 20.0, 60.0, 10.0);
+        let centers: Vec<f64> = boxes
+            .iter()
+            .map(|bbox| f64::midpoint(bbox.min_x, bbox.max_x).round())
+            .collect();
+        assert_eq!(centers, [25.0, 30.0, 35.0, 40.0, 45.0, 50.0, 55.0]);
+        assert!(
+            boxes
+                .iter()
+                .all(|bbox| bbox.min_y < 0.0 && bbox.max_y > 0.0)
+        );
+    }
+
+    #[test]
+    fn has_no_boxes_without_a_positive_size() {
+        let line = [(0.0, 0.0), (100.0, 0.0)];
+        let lengths = cumulative_lengths(&line);
+        assert!(boxes_along(&line, &lengths, 0.0, 100.0, 0.0).is_empty());
+        assert!(boxes_along(&line, &lengths, 0.0, 100.0, -1.0).is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+//! This module provides functionality for laying out the map and its decorations in a document.
+
+use svg::{Document, Node, node::element};
+
+use super::frame::draw_frame;
+use super::legend::{draw_legend, legend_size};
+use super::north_arrow::draw_north_arrow;
+use super::overlay::Overlay;
+use super::scale_bar::draw_scale_bar;
+use super::styles::{
+    Corner, Frame, Legend, LegendPlacement, NorthArrow, ScaleBar, Style, TitleBlock,
+};
+use super::title::{draw_title_block, title_bands};
+use super::{Map, SkippedFeatures, draw_basemap_with_overlays_reporting};
+
+/// Id of the clip path that keeps the features of the map out of the margin
+const MAP_CLIP_ID: &str = "map-clip";
+
+/// The user overlays and the decorations drawn around and on top of the map
+pub struct Layout<'a> {
+    /// The user geometries drawn with the basemap
+    pub overlays: Vec<Overlay<'a>>,
+    /// The frame around the map, `None` for no frame
+    pub frame: Option<Frame<'a>>,
+    /// The scale bar in a corner of the map, `None` for no scale bar
+    pub scale_bar: Option<ScaleBar<'a>>,
+    /// The legend of the layers of the style, `None` for no legend
+    pub legend: Option<Legend<'a>>,
+    /// The north arrow or compass rose, `None` for no north arrow
+    pub north_arrow: Option<NorthArrow<'a>>,
+    /// The title, subtitle, attribution and date around the map, `None` for none of them
+    pub title_block: Option<TitleBlock<'a>>,
+    /// The color of the margin around the map, `None` to keep it transparent
+    pub background: Option<&'a str>,
+}
+
+impl Default for Layout<'_> {
+    /// The bare map without any overlays or decorations
+    fn default() -> Self {
+        Layout {
+            overlays: Vec::new(),
+            frame: None,
+            scale_bar: None,
+            legend: None,
+            north_arrow: None,
+            title_block: None,
+            background: Some("white"),
+        }
+    }
+}
+
+/// The space in pixels the decorations need on each side of the map
+#[derive(Clone, Copy, Default)]
+pub struct Margins {
+    /// Space above the map
+    pub top: f64,
+    /// Space right of the map
+    pub right: f64,
+    /// Space below the map
+    pub bottom: f64,
+    /// Space left of the map
+    pub left: f64,
+}
+
+impl Margins {
+    /// Returns whether the margins leave no space around the map
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.top <= 0.0 && self.right <= 0.0 && self.bottom <= 0.0 && self.left <= 0.0
+    }
+}
+
+impl Layout<'_> {
+    /// Returns the space the decorations need around the map drawn with a style
+    ///
+    /// A legend beside the map goes right of the frame, the bands of the title block above and below it.
+    #[must_use]
+    pub fn margins(&self, style: &Style) -> Margins {
+        let mut margins = Margins::default();
+        if let Some(frame) = &self.frame {
+            margins.top += frame.margin;
+            margins.right += frame.margin;
+            margins.bottom += frame.margin;
+            margins.left += frame.margin;
+        }
+        if let Some(legend) = &self.legend {
+            let (width, _) = legend_size(style, legend);
+            if legend.placement == LegendPlacement::Beside && width > 0.0 {
+                margins.right += width + 2.0 * legend.padding;
+            }
+        }
+        if let Some(title_block) = &self.title_block {
+            let (top, bottom) = title_bands(title_block);
+            margins.top += top;
+            margins.bottom += bottom;
+        }
+        margins
+    }
+}
+
+/// Returns the top left corner of a box of the given size in a corner of the map,
+/// keeping a distance to the map edges
+#[must_use]
+pub fn corner_position(map: &Map, corner: Corner, size: (f64, f64), padding: f64) -> (f64, f64) {
+    let right = f64::from(map.cols) - padding - size.0;
+    let bottom = f64::from(map.rows) - padding - size.1;
+    match corner {
+        Corner::TopLeft => (padding, padding),
+        Corner::TopRight => (right, padding),
+        Corner::BottomLeft => (padding, bottom),
+        Corner::BottomRight => (right, bottom),
+    }
+}
+
+/// Draws the basemap with its overlays and decorations into a new document
+///
+/// The map keeps its pixel coordinates, the view box grows by the margins around it.
+/// With margins, the features of the map are clipped to the map area.
+///
+/// # Arguments
+/// * `map` - The map dimensions and projection settings
+/// * `style` - The style to use for the basemap
+/// * `layout` - The decorations around the map
+///
+/// # Panics
+/// Panics if a shapefile of the style cannot be loaded.
+#[must_use]
+pub fn draw_layout<'a>(map: &Map, style: &Style<'a>, layout: &Layout<'a>) -> Document {
+    draw_layout_reporting(map, style, layout).0
+}
+
+/// Draws the basemap with its decorations like [`draw_layout`] and reports the features it skipped
+///
+/// Returns the document with the layers with skipped features, see [`SkippedFeatures`].
+///
+/// # Panics
+/// Panics if a shapefile of the style cannot be loaded.
+#[must_use]
+pub fn draw_layout_reporting<'a>(
+    map: &Map,
+    style: &Style<'a>,
+    layout: &Layout<'a>,
+) -> (Document, Vec<SkippedFeatures<'a>>) {
+    let margins = layout.margins(style);
+    let width = f64::from(map.cols);
+    let height = f64::from(map.rows);
+    let mut document = Document::new().set(
+        "viewBox",
+        (
+            -margins.left,
+            -margins.top,
+            width + margins.left + margins.right,
+            height + margins.top + margins.bottom,
+        ),
+    );
+
+    let skipped = if margins.is_empty() {
+        draw_basemap_with_overlays_reporting(map, &mut document, style, &layout.overlays)
+    } else {
+        if let Some(background) = layout.background {
+            document.append(
+                element::Rectangle::new()
+                    .set("x", -margins.left)
+                    .set("y", -margins.top)
+                    .set("width", width + margins.left + margins.right)
+                    .set("height", height + margins.top + margins.bottom)
+                    .set("fill", background),
+            );
+        }
+        let clip_path = element::ClipPath::new().set("id", MAP_CLIP_ID).add(
+            element::Rectangle::new()
+                .set("width", width)
+                .set("height", height),
+        );
+        document.append(element::Definitions::new().add(clip_path));
+
+        let mut group = element::Group::new().set("clip-path", format!("url(#{MAP_CLIP_ID})"));
+        let skipped =
+            draw_basemap_with_overlays_reporting(map, &mut group, style, &layout.overlays);
+        document.append(group);
+        skipped
+    };
+
+    if let Some(frame) = &layout.frame {
+        draw_frame(map, &mut document, frame, &style.graticule);
+    }
+    if let Some(scale_bar) = &layout.scale_bar {
+        draw_scale_bar(map, &mut document, scale_bar);
+    }
+    if let Some(legend) = &layout.legend {
+        let size = legend_size(style, legend);
+        let position = match legend.placement {
+            LegendPlacement::Inside(corner) => corner_position(map, corner, size, legend.padding),
+            LegendPlacement::Beside => {
+                let frame_margin = layout.frame.as_ref().map_or(0.0, |frame| frame.margin);
+                (width + frame_margin + legend.padding, 0.0)
+            }
+        };
+        draw_legend(&mut document, style, legend, position);
+    }
+    if let Some(north_arrow) = &layout.north_arrow {
+        draw_north_arrow(map, &mut document, north_arrow);
+    }
+    if let Some(title_block) = &layout.title_block {
+        let frame_margin = layout.frame.as_ref().map_or(0.0, |frame| frame.margin);
+        let (top, _) = title_bands(title_block);
+        let bands = (-frame_margin - top, height + frame_margin);
+        draw_title_block(map, &mut document, title_block, bands);
+    }
+    (document, skipped)
+}
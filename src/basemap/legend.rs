@@ -0,0 +1,228 @@
+//! This module provides functionality for drawing a legend of the layers of a map style.
+
+use svg::{Node, node::element};
+
+use super::draw_svg::{
+    add_to_stroke_passes, draw_label, draw_marker, draw_polygon, draw_polyline, stroke_pass_groups,
+};
+use super::labels::{BASELINE, text_width};
+use super::styles::{LabelStyle, Layer, LayerStyle, Legend, Style};
+
+/// Vertical space in pixels between two rows of the legend
+const ROW_GAP: f64 = 4.0;
+/// Horizontal space in pixels between a sample and its text
+const SWATCH_GAP: f64 = 6.0;
+/// Font size of headings relative to the font size of the entries
+const HEADING_SCALE: f64 = 1.2;
+
+/// A row of the legend
+enum Row<'s> {
+    /// The title of the legend or of a group of layers
+    Heading(&'s str),
+    /// A layer with the text next to its sample
+    Entry {
+        text: String,
+        layer_style: &'s LayerStyle<'s>,
+    },
+}
+
+/// Returns the width and height of the legend in pixels, zero if no layer has a title
+#[must_use]
+pub fn legend_size(style: &Style, legend: &Legend) -> (f64, f64) {
+    let rows = rows(style, legend);
+    if rows.is_empty() {
+        return (0.0, 0.0);
+    }
+    let font_size = legend.label.font_size;
+    let mut width: f64 = 0.0;
+    let mut height = 0.0;
+    for row in &rows {
+        match row {
+            Row::Heading(text) => {
+                width = width.max(text_width(text, HEADING_SCALE * font_size));
+                height += HEADING_SCALE * font_size + ROW_GAP;
+            }
+            Row::Entry { text, .. } => {
+                width = width.max(legend.swatch_size.0 + SWATCH_GAP + text_width(text, font_size));
+                height += entry_height(legend) + ROW_GAP;
+            }
+        }
+    }
+    (
+        width + 2.0 * legend.padding,
+        height - ROW_GAP + 2.0 * legend.padding,
+    )
+}
+
+/// Draws the legend of the layers of a style
+///
+/// The layers are listed in the order they are drawn, followed by the background.
+/// Layers with the same title are grouped under a heading with that title,
+/// each listed with its description. A single layer is listed with its title
+/// and its description in parentheses.
+/// Nothing is drawn if no layer has a title.
+///
+/// # Arguments
+/// * `document` - The SVG document or group to modify
+/// * `style` - The style whose layers are listed
+/// * `legend` - The style of the legend
+/// * `position` - The top left corner of the legend box
+pub fn draw_legend(document: &mut impl Node, style: &Style, legend: &Legend, position: (f64, f64)) {
+    let rows = rows(style, legend);
+    if rows.is_empty() {
+        return;
+    }
+
+    let (width, height) = legend_size(style, legend);
+    let frame = element::Rectangle::new()
+        .set("x", position.0)
+        .set("y", position.1)
+        .set("width", width)
+        .set("height", height)
+        .set("fill", legend.background.unwrap_or("none"))
+        .set("stroke", legend.stroke)
+        .set("stroke-width", 0.5);
+    document.append(frame);
+
+    let font_size = legend.label.font_size;
+    let fill = legend.label.fill.unwrap_or("black");
+    let heading_style = LabelStyle {
+        font_size: HEADING_SCALE * font_size,
+        font_weight: "bold",
+        text_anchor: "start",
+        ..legend.label.clone()
+    };
+    let entry_style = LabelStyle {
+        text_anchor: "start",
+        ..legend.label.clone()
+    };
+
+    let x = position.0 + legend.padding;
+    let mut y = position.1 + legend.padding;
+    for row in &rows {
+        match row {
+            Row::Heading(text) => {
+                let row_height = HEADING_SCALE * font_size;
+                let baseline = y + row_height / 2.0 + BASELINE * row_height;
+                draw_label((x, baseline), text, document, &heading_style, fill);
+                y += row_height + ROW_GAP;
+            }
+            Row::Entry { text, layer_style } => {
+                let row_height = entry_height(legend);
+                let center = y + row_height / 2.0;
+                draw_swatch(document, layer_style, (x, center), legend.swatch_size);
+                let text_x = x + legend.swatch_size.0 + SWATCH_GAP;
+                let baseline = center + BASELINE * font_size;
+                draw_label((text_x, baseline), text, document, &entry_style, fill);
+                y += row_height + ROW_GAP;
+            }
+        }
+    }
+}
+
+/// Returns the rows of the legend, empty if no layer has a title
+fn rows<'s>(style: &'s Style, legend: &'s Legend) -> Vec<Row<'s>> {
+    let layers: Vec<&Layer> = style
+        .layers
+        .iter()
+        .chain(std::iter::once(&style.background))
+        .filter(|layer| layer.title.is_some())
+        .collect();
+
+    let mut titles: Vec<&str> = Vec::new();
+    for title in layers.iter().filter_map(|layer| layer.title) {
+        if !titles.contains(&title) {
+            titles.push(title);
+        }
+    }
+    if titles.is_empty() {
+        return Vec::new();
+    }
+
+    let mut rows: Vec<Row> = legend.title.map(Row::Heading).into_iter().collect();
+    for title in titles {
+        let group: Vec<&Layer> = layers
+            .iter()
+            .filter(|layer| layer.title == Some(title))
+            .copied()
+            .collect();
+        if let [layer] = group.as_slice() {
+            let text = match layer.description {
+                Some(description) => format!("{title} ({description})"),
+                None => title.to_string(),
+            };
+            rows.push(Row::Entry {
+                text,
+                layer_style: &layer.layer_style,
+            });
+        } else {
+            rows.push(Row::Heading(title));
+            for layer in group {
+                rows.push(Row::Entry {
+                    text: layer.description.unwrap_or(title).to_string(),
+                    layer_style: &layer.layer_style,
+                });
+            }
+        }
+    }
+    rows
+}
+
+/// Draws the sample of a layer style: its marker, a line if it has no fill, an area otherwise
+///
+/// # Arguments
+/// * `document` - The SVG document or group to modify
+/// * `layer_style` - The style to sample
+/// * `left_center` - The middle of the left edge of the sample
+/// * `size` - The width and height of the sample
+fn draw_swatch(
+    document: &mut impl Node,
+    layer_style: &LayerStyle,
+    left_center: (f64, f64),
+    size: (f64, f64),
+) {
+    let (x, y) = left_center;
+    let (width, height) = size;
+    if let Some(marker) = &layer_style.marker {
+        draw_marker(
+            (x + width / 2.0, y),
+            marker.size.min(height),
+            document,
+            marker,
+        );
+    } else if layer_style.fill == "none"
+        && layer_style.fill_pattern.is_none()
+        && layer_style.fill_gradient.is_none()
+    {
+        let points = [(x, y), (x + width, y)];
+        draw_swatch_passes(document, layer_style, &points);
+        draw_polyline(&points, document, layer_style, element::path::Data::new());
+    } else {
+        let top = y - height / 2.0;
+        let bottom = y + height / 2.0;
+        // Closed by repeating the first point, like the rings of a shapefile, so the stroke goes all around
+        let points = [
+            (x, top),
+            (x + width, top),
+            (x + width, bottom),
+            (x, bottom),
+            (x, top),
+        ];
+        draw_swatch_passes(document, layer_style, &points);
+        draw_polygon(&points, document, layer_style, element::path::Data::new());
+    }
+}
+
+/// Draws the stroke passes of a style beneath a sample, like beneath the features of its layer
+fn draw_swatch_passes(document: &mut impl Node, layer_style: &LayerStyle, points: &[(f64, f64)]) {
+    let mut pass_groups = stroke_pass_groups(&layer_style.stroke_passes);
+    add_to_stroke_passes(&mut pass_groups, points);
+    for pass_group in pass_groups {
+        document.append(pass_group);
+    }
+}
+
+/// Returns the height of an entry row, fitting both the sample and the text
+fn entry_height(legend: &Legend) -> f64 {
+    legend.swatch_size.1.max(legend.label.font_size)
+}
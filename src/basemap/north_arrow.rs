@@ -0,0 +1,191 @@
+//! This module provides functionality for drawing north arrows and compass roses.
+
+use svg::{Node, node::element};
+
+use super::Map;
+use super::draw_svg::draw_label;
+use super::labels::BASELINE;
+use super::layout::corner_position;
+use super::styles::{LabelStyle, NorthArrow, NorthArrowKind, Placement};
+use super::utils::{inverse_mapping_function, mapping_function};
+
+/// Degrees of latitude over which the direction of north is measured
+const NORTH_STEP: f64 = 0.01;
+/// Gap in pixels between the arrow and its letters
+const LABEL_GAP: f64 = 2.0;
+/// Length of the intercardinal points of a compass rose relative to the cardinal ones
+const INTERCARDINAL_LENGTH: f64 = 0.45;
+/// Half width of the base of a compass rose point relative to its length
+const POINT_WIDTH: f64 = 0.18;
+/// Radius of the ring of a compass rose relative to the length of its cardinal points
+const RING_RADIUS: f64 = 0.55;
+
+/// Draws a north arrow or compass rose pointing to true north at its position
+///
+/// The direction of north is taken from the projection of the map at the position of the arrow,
+/// so the arrow leans on maps where the meridians are not vertical.
+/// Nothing is drawn if the position lies outside the projected globe.
+///
+/// # Arguments
+/// * `map` - The map dimensions and projection settings
+/// * `document` - The SVG document or group to modify
+/// * `north_arrow` - The style and position of the arrow
+pub fn draw_north_arrow(map: &Map, document: &mut impl Node, north_arrow: &NorthArrow) {
+    let center = match north_arrow.placement {
+        Placement::Corner(corner) => {
+            let extent = extent(north_arrow);
+            let (x, y) = corner_position(map, corner, (extent, extent), north_arrow.padding);
+            (x + extent / 2.0, y + extent / 2.0)
+        }
+        Placement::Pixel(x, y) => (x, y),
+    };
+    let Some(angle) = north_angle(map, center) else {
+        return;
+    };
+
+    let mut group = element::Group::new().set(
+        "transform",
+        format!("translate({} {}) rotate({angle})", center.0, center.1),
+    );
+    match north_arrow.kind {
+        NorthArrowKind::Arrow => draw_arrow(&mut group, north_arrow),
+        NorthArrowKind::CompassRose => draw_compass_rose(&mut group, north_arrow),
+    }
+    document.append(group);
+}
+
+/// Returns the clockwise angle in degrees from straight up to true north at a pixel position,
+/// `None` if the position lies outside the projected globe
+#[must_use]
+pub fn north_angle(map: &Map, position: (f64, f64)) -> Option<f64> {
+    let (lon, lat) = inverse_mapping_function(position.0, position.1, map)?;
+    // Close to the pole, the step ends at the pole instead of beyond it
+    let (from, to) = if lat + NORTH_STEP <= 90.0 {
+        (lat, lat + NORTH_STEP)
+    } else {
+        (lat - NORTH_STEP, lat)
+    };
+    let (x1, y1) = mapping_function(lon, from, map);
+    let (x2, y2) = mapping_function(lon, to, map);
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    if dx.hypot(dy) < f64::EPSILON {
+        return None;
+    }
+    Some(dx.atan2(-dy).to_degrees())
+}
+
+/// Draws an arrow pointing up from the origin, split into a dark and a light half
+fn draw_arrow(document: &mut impl Node, north_arrow: &NorthArrow) {
+    let half = north_arrow.size / 2.0;
+    let tip = (0.0, -half);
+    let notch = (0.0, half / 2.0);
+    document.append(point_half(
+        &[tip, (-half / 2.0, half), notch],
+        north_arrow.fill,
+        north_arrow,
+    ));
+    document.append(point_half(
+        &[tip, (half / 2.0, half), notch],
+        north_arrow.light_fill,
+        north_arrow,
+    ));
+
+    if let Some(label_style) = &north_arrow.label {
+        draw_letter(
+            document,
+            north_arrow,
+            label_style,
+            "N",
+            (0.0, -half - LABEL_GAP),
+        );
+    }
+}
+
+/// Draws a compass rose around the origin with its north point up
+fn draw_compass_rose(document: &mut impl Node, north_arrow: &NorthArrow) {
+    let half = north_arrow.size / 2.0;
+    let ring = element::Circle::new()
+        .set("r", RING_RADIUS * half)
+        .set("fill", "none")
+        .set("stroke", north_arrow.stroke)
+        .set("stroke-width", 0.75);
+    document.append(ring);
+
+    // The intercardinal points go beneath the cardinal ones
+    for (length, first) in [(INTERCARDINAL_LENGTH * half, 1), (half, 0)] {
+        for index in (first..8u32).step_by(2) {
+            let angle = f64::from(index) * 45f64.to_radians();
+            let (dx, dy) = (angle.sin(), -angle.cos());
+            let tip = (dx * length, dy * length);
+            let width = POINT_WIDTH * length;
+            let left = (dy * width, -dx * width);
+            let right = (-dy * width, dx * width);
+            document.append(point_half(
+                &[(0.0, 0.0), tip, left],
+                north_arrow.fill,
+                north_arrow,
+            ));
+            document.append(point_half(
+                &[(0.0, 0.0), tip, right],
+                north_arrow.light_fill,
+                north_arrow,
+            ));
+        }
+    }
+
+    if let Some(label_style) = &north_arrow.label {
+        let font_size = label_style.font_size;
+        let distance = half + LABEL_GAP;
+        draw_letter(document, north_arrow, label_style, "N", (0.0, -distance));
+        draw_letter(
+            document,
+            north_arrow,
+            label_style,
+            "S",
+            (0.0, distance + 2.0 * BASELINE * font_size),
+        );
+        let middle = BASELINE * font_size;
+        let east = LabelStyle {
+            text_anchor: "start",
+            ..label_style.clone()
+        };
+        draw_letter(document, north_arrow, &east, "E", (distance, middle));
+        let west = LabelStyle {
+            text_anchor: "end",
+            ..label_style.clone()
+        };
+        draw_letter(document, north_arrow, &west, "W", (-distance, middle));
+    }
+}
+
+/// Returns a triangle of an arrow point
+fn point_half(points: &[(f64, f64)], fill: &str, north_arrow: &NorthArrow) -> element::Polygon {
+    let points: Vec<String> = points.iter().map(|(x, y)| format!("{x},{y}")).collect();
+    element::Polygon::new()
+        .set("points", points.join(" "))
+        .set("fill", fill)
+        .set("stroke", north_arrow.stroke)
+        .set("stroke-width", 0.75)
+        .set("stroke-linejoin", "round")
+}
+
+/// Draws a direction letter with its baseline at a position
+fn draw_letter(
+    document: &mut impl Node,
+    north_arrow: &NorthArrow,
+    label_style: &LabelStyle,
+    letter: &str,
+    position: (f64, f64),
+) {
+    let fill = label_style.fill.unwrap_or(north_arrow.fill);
+    draw_label(position, letter, document, label_style, fill);
+}
+
+/// Returns the width and height of the square the arrow and its letters fit in
+fn extent(north_arrow: &NorthArrow) -> f64 {
+    let letters = north_arrow
+        .label
+        .as_ref()
+        .map_or(0.0, |label_style| 2.0 * (label_style.font_size + LABEL_GAP));
+    north_arrow.size + letters
+}
@@ -0,0 +1,208 @@
+//! This module provides functionality for drawing user geometries on top of the basemap.
+
+#[cfg(any(feature = "csv", feature = "geojson"))]
+use std::path::Path;
+
+use geo::{Geometry, LineString, MultiLineString, MultiPolygon, Polygon};
+use shapefile::dbase::Record;
+use shapefile::{Multipoint, Point, Polyline, Shape};
+
+#[cfg(feature = "csv")]
+use super::csv_points::{CsvColumns, read_csv_points};
+#[cfg(feature = "geojson")]
+use super::sources::read_geojson;
+#[cfg(feature = "csv")]
+use super::styles::LabelStyle;
+use super::styles::LayerStyle;
+
+/// Where an overlay is drawn relative to the layers of the map style
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ZOrder {
+    /// Right above the background, beneath all layers
+    BelowLayers,
+    /// Above the layer with the given index in the style, beneath the next one;
+    /// an index past the last layer draws the overlay above the last layer,
+    /// but unlike [`ZOrder::AboveLayers`] still beneath the graticule
+    AboveLayer(usize),
+    /// Above all layers and the graticule, beneath the labels
+    AboveLayers,
+}
+
+/// User geometries drawn on the basemap with a style of their own
+///
+/// The coordinates are degrees of longitude and latitude. The geometries are projected
+/// and styled like the features of a shapefile layer, and their labels are placed
+/// together with the labels of the layers.
+/// Geometries reaching past the map are not clipped to it by the overlay: only the clip path
+/// of a layout with margins, or else the view box of the document, cuts them at the map edge.
+pub struct Overlay<'a> {
+    /// The geometries with their attributes, e.g. a "name" for labels
+    pub features: Vec<(Geometry<f64>, Record)>,
+    /// The style of the geometries
+    pub layer_style: LayerStyle<'a>,
+    /// Where the overlay is drawn relative to the layers of the map style
+    pub z_order: ZOrder,
+    /// The number of features of the source file left out for a malformed geometry or position
+    pub skipped: usize,
+}
+
+impl<'a> Overlay<'a> {
+    /// Creates an overlay of geometries without attributes, drawn above all layers
+    #[must_use]
+    pub fn new(
+        geometries: impl IntoIterator<Item = Geometry<f64>>,
+        layer_style: LayerStyle<'a>,
+    ) -> Self {
+        Overlay {
+            features: geometries
+                .into_iter()
+                .map(|geometry| (geometry, Record::default()))
+                .collect(),
+            layer_style,
+            z_order: ZOrder::AboveLayers,
+            skipped: 0,
+        }
+    }
+
+    /// Creates an overlay of the features of a `GeoJSON` file, drawn above all layers
+    ///
+    /// The properties of the features become their attributes, so they can be labeled.
+    /// Features with a malformed geometry are left out and counted in [`Overlay::skipped`].
+    ///
+    /// # Panics
+    /// Panics if the file cannot be read or is no valid `GeoJSON`.
+    #[cfg(feature = "geojson")]
+    #[must_use]
+    pub fn from_geojson(path: &Path, layer_style: LayerStyle<'a>) -> Self {
+        let (features, skipped) = read_geojson(path);
+        Overlay {
+            features,
+            layer_style,
+            z_order: ZOrder::AboveLayers,
+            skipped,
+        }
+    }
+
+    /// Creates an overlay of the rows of a CSV file as points, drawn above all layers
+    ///
+    /// The points are drawn with the marker of the style and labeled by the label column,
+    /// all columns are attributes, e.g. for the marker size.
+    /// Rows without a valid position are left out and counted in [`Overlay::skipped`].
+    ///
+    /// # Panics
+    /// Panics if the file cannot be read, or lacks the latitude or longitude column.
+    #[cfg(feature = "csv")]
+    #[must_use]
+    pub fn from_csv(path: &Path, columns: &CsvColumns<'a>, layer_style: LayerStyle<'a>) -> Self {
+        let label = LabelStyle {
+            field: columns.label.unwrap_or(layer_style.label.field),
+            ..layer_style.label.clone()
+        };
+        let (features, skipped) = read_csv_points(path, columns);
+        Overlay {
+            features,
+            layer_style: LayerStyle {
+                label,
+                ..layer_style
+            },
+            z_order: ZOrder::AboveLayers,
+            skipped,
+        }
+    }
+
+    /// Returns the features as shapes with their records, the form shapefile layers are drawn from
+    ///
+    /// See [`geometry_shapes`] for the conversion; the shapes of a feature share its record.
+    #[must_use]
+    pub fn shapes(&self) -> Vec<(Shape, Record)> {
+        let mut shapes = Vec::new();
+        for (geometry, record) in &self.features {
+            let feature_shapes = geometry_shapes(geometry.clone());
+            shapes.extend(
+                feature_shapes
+                    .into_iter()
+                    .map(|shape| (shape, record.clone())),
+            );
+        }
+        shapes
+    }
+}
+
+/// Converts a geometry to shapes
+///
+/// Geometry collections are split into their members.
+/// Degenerate geometries, like lines of a single point or polygons of less than three points, are left out.
+#[must_use]
+pub fn geometry_shapes(geometry: Geometry<f64>) -> Vec<Shape> {
+    let mut shapes = Vec::new();
+    push_shapes(geometry, &mut shapes);
+    shapes
+}
+
+/// Returns the number of layers of the style drawn beneath an overlay,
+/// `None` if the overlay goes above the graticule too
+///
+/// The index of [`ZOrder::AboveLayer`] is clamped to the layers of the style,
+/// so an index past the last layer puts all layers beneath the overlay.
+#[must_use]
+pub fn layers_below(z_order: ZOrder, layer_count: usize) -> Option<usize> {
+    match z_order {
+        ZOrder::BelowLayers => Some(0),
+        ZOrder::AboveLayer(index) => Some((index + 1).min(layer_count)),
+        ZOrder::AboveLayers => None,
+    }
+}
+
+/// Converts a geometry to shapes and appends them
+fn push_shapes(geometry: Geometry<f64>, shapes: &mut Vec<Shape>) {
+    match geometry {
+        Geometry::Point(point) => shapes.push(Shape::Point(Point::from(point))),
+        Geometry::MultiPoint(multi_point) => {
+            if !multi_point.0.is_empty() {
+                shapes.push(Shape::Multipoint(Multipoint::from(multi_point)));
+            }
+        }
+        Geometry::Line(line) => shapes.push(Shape::Polyline(Polyline::from(line))),
+        Geometry::LineString(line_string) => push_lines(vec![line_string], shapes),
+        Geometry::MultiLineString(multi_line_string) => push_lines(multi_line_string.0, shapes),
+        Geometry::Polygon(polygon) => push_polygons(vec![polygon], shapes),
+        Geometry::MultiPolygon(multi_polygon) => push_polygons(multi_polygon.0, shapes),
+        Geometry::Rect(rect) => push_polygons(vec![rect.to_polygon()], shapes),
+        Geometry::Triangle(triangle) => push_polygons(vec![triangle.to_polygon()], shapes),
+        Geometry::GeometryCollection(collection) => {
+            for geometry in collection {
+                push_shapes(geometry, shapes);
+            }
+        }
+    }
+}
+
+/// Appends the lines with at least two points as one polyline
+fn push_lines(lines: Vec<LineString<f64>>, shapes: &mut Vec<Shape>) {
+    let lines: Vec<LineString<f64>> = lines.into_iter().filter(|line| line.0.len() >= 2).collect();
+    if !lines.is_empty() {
+        shapes.push(Shape::Polyline(Polyline::from(MultiLineString::new(lines))));
+    }
+}
+
+/// Appends the polygons with an exterior of at least three points as one polygon shape,
+/// leaving out their interiors of less than three points
+fn push_polygons(polygons: Vec<Polygon<f64>>, shapes: &mut Vec<Shape>) {
+    let polygons: Vec<Polygon<f64>> = polygons
+        .into_iter()
+        .filter(|polygon| polygon.exterior().0.len() >= 3)
+        .map(|polygon| {
+            let (exterior, interiors) = polygon.into_inner();
+            let interiors = interiors
+                .into_iter()
+                .filter(|interior| interior.0.len() >= 3)
+                .collect();
+            Polygon::new(exterior, interiors)
+        })
+        .collect();
+    if !polygons.is_empty() {
+        shapes.push(Shape::Polygon(shapefile::Polygon::from(MultiPolygon::new(
+            polygons,
+        ))));
+    }
+}
@@ -0,0 +1,202 @@
+//! Visual centers of polygons
+//!
+//! Finds the pole of inaccessibility, the interior point furthest from the outline,
+//! by refining a grid of cells as in Mapbox's polylabel.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use geo::{BoundingRect, Centroid, Coord, Polygon};
+
+/// A square cell of the search grid
+struct Cell {
+    /// The center of the cell
+    center: Coord<f64>,
+    /// Half the side length of the cell
+    half: f64,
+    /// The signed distance from the center to the outline, negative outside the polygon
+    distance: f64,
+    /// The largest distance to the outline any point in the cell can have
+    max: f64,
+}
+
+impl Cell {
+    fn new(center: Coord<f64>, half: f64, polygon: &Polygon<f64>) -> Self {
+        let distance = signed_distance(center, polygon);
+        Cell {
+            center,
+            half,
+            distance,
+            max: distance + half * std::f64::consts::SQRT_2,
+        }
+    }
+}
+
+impl PartialEq for Cell {
+    fn eq(&self, other: &Self) -> bool {
+        self.max.total_cmp(&other.max) == Ordering::Equal
+    }
+}
+
+impl Eq for Cell {}
+
+impl PartialOrd for Cell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.max.total_cmp(&other.max)
+    }
+}
+
+/// Returns the pole of inaccessibility of a polygon
+///
+/// # Arguments
+/// * `polygon` - The polygon, with holes
+/// * `precision` - The distance within which the result is as far from the outline as the true pole
+///
+/// Returns `None` for an empty polygon.
+#[must_use]
+pub fn polylabel(polygon: &Polygon<f64>, precision: f64) -> Option<Coord<f64>> {
+    let rect = polygon.bounding_rect()?;
+    let cell_size = rect.width().min(rect.height());
+    if cell_size <= 0.0 {
+        return Some(rect.min());
+    }
+    let half = cell_size / 2.0;
+
+    // Cover the bounding box with cells
+    let mut cells = BinaryHeap::new();
+    let mut x = rect.min().x;
+    while x < rect.max().x {
+        let mut y = rect.min().y;
+        while y < rect.max().y {
+            cells.push(Cell::new(
+                Coord {
+                    x: x + half,
+                    y: y + half,
+                },
+                half,
+                polygon,
+            ));
+            y += cell_size;
+        }
+        x += cell_size;
+    }
+
+    // The centroid is a good first guess for most shapes
+    let mut best = Cell::new(rect.center(), 0.0, polygon);
+    if let Some(centroid) = polygon.centroid() {
+        let centroid = Cell::new(centroid.0, 0.0, polygon);
+        if centroid.distance > best.distance {
+            best = centroid;
+        }
+    }
+
+    while let Some(cell) = cells.pop() {
+        if cell.distance > best.distance {
+            best = Cell::new(cell.center, 0.0, polygon);
+        }
+        // No point in this cell can be meaningfully better than the best so far
+        if cell.max - best.distance <= precision {
+            continue;
+        }
+        let half = cell.half / 2.0;
+        for (dx, dy) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+            let center = Coord {
+                x: cell.center.x + dx * half,
+                y: cell.center.y + dy * half,
+            };
+            cells.push(Cell::new(center, half, polygon));
+        }
+    }
+
+    Some(best.center)
+}
+
+/// The distance from a point to the outline of a polygon, negative outside the polygon
+fn signed_distance(point: Coord<f64>, polygon: &Polygon<f64>) -> f64 {
+    let mut inside = false;
+    let mut min_distance = f64::INFINITY;
+
+    for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+        for line in ring.lines() {
+            let (a, b) = (line.start, line.end);
+            // Even-odd ray casting toward positive x
+            if (a.y > point.y) != (b.y > point.y)
+                && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+            {
+                inside = !inside;
+            }
+            min_distance = min_distance.min(segment_distance(point, a, b));
+        }
+    }
+
+    if inside { min_distance } else { -min_distance }
+}
+
+/// The distance from a point to the segment from `a` to `b`
+fn segment_distance(point: Coord<f64>, a: Coord<f64>, b: Coord<f64>) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared > 0.0 {
+        (((point.x - a.x) * dx + (point.y - a.y) * dy) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (point.x - (a.x + t * dx)).hypot(point.y - (a.y + t * dy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::{LineString, polygon};
+
+    const PRECISION: f64 = 0.1;
+
+    #[test]
+    fn finds_the_center_of_a_square() {
+        let square =
+            polygon![(x: 0.0, y: 0.0), (x: 10.0, y: 0.0), (x: 10.0, y: 10.0), (x: 0.0, y: 10.0)];
+        let pole = polylabel(&square, PRECISION).expect("A square has a pole");
+        assert!((pole.x - 5.0).abs() < PRECISION && (pole.y - 5.0).abs() < PRECISION);
+    }
+
+    #[test]
+    fn stays_inside_a_concave_polygon_whose_centroid_is_outside() {
+        // A "C" opening to the right, with bars two wide
+        let c = polygon![
+            (x: 0.0, y: 0.0), (x: 10.0, y: 0.0), (x: 10.0, y: 2.0), (x: 2.0, y: 2.0),
+            (x: 2.0, y: 8.0), (x: 10.0, y: 8.0), (x: 10.0, y: 10.0), (x: 0.0, y: 10.0),
+        ];
+        let centroid = c.centroid().expect("The C has a centroid").0;
+        assert!(signed_distance(centroid, &c) < 0.0);
+
+        let pole = polylabel(&c, PRECISION).expect("The C has a pole");
+        assert!(signed_distance(pole, &c) > 1.0 - PRECISION);
+    }
+
+    #[test]
+    fn keeps_out_of_holes() {
+        let exterior = LineString::from(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+        let hole = LineString::from(vec![(3.0, 3.0), (7.0, 3.0), (7.0, 7.0), (3.0, 7.0)]);
+        let ring = Polygon::new(exterior, vec![hole]);
+
+        let pole = polylabel(&ring, PRECISION).expect("The ring has a pole");
+        // The band around the hole is three wide, so the pole is at most 1.5 from the outline
+        assert!(signed_distance(pole, &ring) > 1.5 - PRECISION);
+        assert!(signed_distance(Coord { x: 5.0, y: 5.0 }, &ring) < 0.0);
+    }
+
+    #[test]
+    fn handles_degenerate_and_empty_rings() {
+        let flat = polygon![(x: 0.0, y: 0.0), (x: 10.0, y: 0.0), (x: 5.0, y: 0.0)];
+        assert_eq!(polylabel(&flat, PRECISION), Some(Coord { x: 0.0, y: 0.0 }));
+
+        let empty = Polygon::new(LineString::new(Vec::new()), Vec::new());
+        assert_eq!(polylabel(&empty, PRECISION), None);
+    }
+}
@@ -0,0 +1,160 @@
+//! This module provides functionality for drawing a scale bar that follows the projection of the map.
+
+use geo::{Distance, Haversine, Point};
+use svg::{Node, node::element};
+
+use super::Map;
+use super::draw_svg::draw_label;
+use super::labels::{DIGIT_HEIGHT, text_width};
+use super::styles::{Corner, LabelStyle, ScaleBar};
+use super::utils::mapping_function;
+
+/// Degrees of longitude over which the scale of the map is measured
+const MEASURE_STEP: f64 = 0.01;
+/// Gap in pixels between the bar and its labels
+const LABEL_GAP: f64 = 3.0;
+
+/// Draws a scale bar in a corner of the map
+///
+/// The scale is measured along the parallel at the latitude of the scale bar,
+/// so it holds for the projection of the map at that latitude.
+/// The bar shows the largest distance of 1, 2 or 5 times a power of ten that fits its maximum length.
+/// Nothing is drawn if the map has no finite scale at that latitude.
+///
+/// # Arguments
+/// * `map` - The map dimensions and projection settings
+/// * `document` - The SVG document or group to modify
+/// * `scale_bar` - The style and position of the scale bar
+pub fn draw_scale_bar(map: &Map, document: &mut impl Node, scale_bar: &ScaleBar) {
+    let lat = scale_bar
+        .latitude
+        .unwrap_or(f64::midpoint(map.lat_min, map.lat_max));
+    let units_per_pixel = meters_per_pixel(map, lat) / scale_bar.unit.meters();
+    if !units_per_pixel.is_finite() || units_per_pixel <= 0.0 {
+        return;
+    }
+    let distance = round_distance(scale_bar.max_length * units_per_pixel);
+    let length = distance / units_per_pixel;
+
+    // The labels go above the bar
+    let label_height = DIGIT_HEIGHT * scale_bar.label.font_size + LABEL_GAP;
+    let x = match scale_bar.corner {
+        Corner::TopLeft | Corner::BottomLeft => scale_bar.padding,
+        Corner::TopRight | Corner::BottomRight => f64::from(map.cols) - scale_bar.padding - length,
+    };
+    let y = match scale_bar.corner {
+        Corner::TopLeft | Corner::TopRight => scale_bar.padding + label_height,
+        Corner::BottomLeft | Corner::BottomRight => {
+            f64::from(map.rows) - scale_bar.padding - scale_bar.height
+        }
+    };
+
+    let segments = scale_bar.segments.max(1);
+    let segment_length = length / f64::from(segments);
+    for index in 0..segments {
+        let color = if index.is_multiple_of(2) {
+            scale_bar.colors.0
+        } else {
+            scale_bar.colors.1
+        };
+        let segment = element::Rectangle::new()
+            .set("x", x + f64::from(index) * segment_length)
+            .set("y", y)
+            .set("width", segment_length)
+            .set("height", scale_bar.height)
+            .set("fill", color)
+            .set("stroke", scale_bar.stroke)
+            .set("stroke-width", 0.5);
+        document.append(segment);
+    }
+
+    let fill = scale_bar.label.fill.unwrap_or(scale_bar.stroke);
+    let baseline = y - LABEL_GAP;
+    let label = |text_anchor| LabelStyle {
+        text_anchor,
+        ..scale_bar.label.clone()
+    };
+    let end = format!("{} {}", format_distance(distance), scale_bar.unit.symbol());
+    draw_label((x, baseline), "0", document, &label("start"), fill);
+    draw_label((x + length, baseline), &end, document, &label("end"), fill);
+
+    // The middle label is left out if it would run into the end label
+    let middle = format_distance(distance / 2.0);
+    let font_size = scale_bar.label.font_size;
+    let needed = text_width(&middle, font_size) / 2.0 + text_width(&end, font_size) + LABEL_GAP;
+    if segments.is_multiple_of(2) && needed <= length / 2.0 {
+        draw_label(
+            (x + length / 2.0, baseline),
+            &middle,
+            document,
+            &label("middle"),
+            fill,
+        );
+    }
+}
+
+/// Returns the ground distance in meters covered by one pixel along the parallel
+/// at a latitude, measured at the central meridian of the map
+#[must_use]
+pub fn meters_per_pixel(map: &Map, lat: f64) -> f64 {
+    let lon = f64::midpoint(map.lon_min, map.lon_max);
+    let west = lon - MEASURE_STEP / 2.0;
+    let east = lon + MEASURE_STEP / 2.0;
+    let (x1, y1) = mapping_function(west, lat, map);
+    let (x2, y2) = mapping_function(east, lat, map);
+    let meters = Haversine.distance(Point::new(west, lat), Point::new(east, lat));
+    meters / (x2 - x1).hypot(y2 - y1)
+}
+
+/// Returns the largest distance of 1, 2 or 5 times a power of ten not exceeding a maximum
+fn round_distance(max: f64) -> f64 {
+    let magnitude = 10f64.powf(max.log10().floor());
+    [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|distance| *distance <= max)
+        .unwrap_or(magnitude)
+}
+
+/// Formats a distance without the rounding noise of floating point numbers
+fn format_distance(distance: f64) -> String {
+    let rounded = (distance * 1e6).round() / 1e6;
+    format!("{rounded}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A map of 100 pixels per degree in both directions
+    fn map() -> Map {
+        Map {
+            rows: 1000,
+            cols: 1000,
+            lat_min: 0.0,
+            lat_max: 10.0,
+            lon_min: 0.0,
+            lon_max: 10.0,
+        }
+    }
+
+    #[test]
+    fn rounds_down_to_one_two_or_five_times_a_power_of_ten() {
+        let rounded: Vec<String> = [1.0, 1.9, 2.0, 4.99, 7.3, 19.9, 250.0, 0.035]
+            .into_iter()
+            .map(|max| format_distance(round_distance(max)))
+            .collect();
+        assert_eq!(rounded, ["1", "1", "2", "2", "5", "10", "200", "0.02"]);
+    }
+
+    #[test]
+    fn measures_a_degree_of_longitude_along_the_parallel() {
+        let map = map();
+        // A degree of longitude at the equator is about 111.2 km, spread over 100 pixels
+        let equator = meters_per_pixel(&map, 0.0);
+        assert!((equator - 1111.95).abs() < 0.1, "{equator}");
+        // and half as long at 60 degrees
+        let north = meters_per_pixel(&map, 60.0);
+        assert!((north / equator - 0.5).abs() < 1e-6, "{north}");
+    }
+}
@@ -3,84 +3,492 @@
 use std::fs::File;
 use std::io::BufReader;
 
-use shapefile::{Point, Reader, Shape};
-use svg::{Document, node::element};
+use geo::{Area, Coord, MapCoords, MultiPolygon};
+use shapefile::dbase::Record;
+use shapefile::record::traits::HasXY;
+use shapefile::{
+    Multipatch, Multipoint, Patch, Point, Polygon, PolygonRing, Polyline, Reader, Shape,
+};
+use svg::{Node, node::element};
 
-use super::draw_svg::{draw_polygon, draw_polyline, draw_text};
+use super::Map;
+use super::attributes;
+use super::defs::{DefinedIds, append_layer_defs, filter_url};
+use super::draw_svg::{
+    add_to_stroke_passes, draw_marker, draw_polygon, draw_polyline, stroke_pass_groups,
+};
+use super::effects::draw_water_lines;
+use super::labels::{Label, LabelPlacer, LineLabel};
+use super::polylabel::polylabel;
+use super::styles::{LabelStyle, LayerStyle};
 use super::utils::mapping_function;
-use super::{Map, styles::LayerStyle};
+
+/// Distance in pixels within which polygon labels are at the visual center
+const POLYLABEL_PRECISION: f64 = 1.0;
+/// Font size of a polygon label per pixel of the square root of the polygon area
+const AREA_FONT_RATIO: f64 = 0.1;
+/// Text color of labels whose style and feature have no color
+const DEFAULT_LABEL_FILL: &str = "black";
 
 /// Visualizes a shapefile by converting its features to SVG paths
 ///
+/// See [`visualize_features`] for how the features are drawn.
+///
+/// # Arguments
+/// * `map` - The map dimensions and projection settings
+/// * `reader` - The shapefile reader containing the features
+/// * `document` - The SVG document or group to modify
+/// * `layer_style` - The style to apply to the features
+/// * `labels` - The placer collecting the labels of the features
+/// * `defined` - The ids of the definitions already in the document
+///
+/// Returns the number of features skipped for lack of a drawable geometry.
+///
+/// # Panics
+/// Panics if there is an error reading data from the shapefile.
+pub fn visualize_shapefile<'a>(
+    map: &Map,
+    mut reader: Reader<BufReader<File>, BufReader<File>>,
+    document: &mut impl Node,
+    layer_style: &LayerStyle<'a>,
+    labels: &mut LabelPlacer<'a>,
+    defined: &mut DefinedIds,
+) -> usize {
+    let features = reader
+        .iter_shapes_and_records()
+        .map(|result| result.expect("Error reading data from shapefile"));
+    visualize_features(map, features, document, layer_style, labels, defined)
+}
+
+/// Visualizes features by converting them to SVG paths
+///
 /// This function handles different types of features:
 /// * Polygons (e.g., land masses, lakes)
 /// * Polylines (e.g., rivers, coastlines)
+/// * Points and multipoints, drawn as markers if the style has any, whose names are
+///   added to the label placer
+///
+/// Polylines are labeled too if the label style has a line spacing,
+/// polygons if the label style asks for polygon labels.
+///
+/// Shapes with z or m values are drawn from their x and y coordinates like their plain counterparts.
+/// Multipatches are drawn as polygons, with triangle strips and fans split into single triangles.
+/// Features without any drawable geometry, like null shapes, are skipped and counted.
+/// Features not meeting the feature filter of the style are left out without being counted.
 ///
 /// Features that fall entirely outside the map bounds are skipped.
+/// If the style has water lines, they are drawn around the polygons before any feature,
+/// so only then are all features of the layer held in memory at once.
+/// The features are grouped, so a filter or blend mode of the style applies to the layer as a whole.
 ///
 /// # Arguments
 /// * `map` - The map dimensions and projection settings
-/// * `reader` - The shapefile reader containing the features
-/// * `document` - The SVG document to modify
+/// * `features` - The shapes in degrees of longitude and latitude with their attributes
+/// * `document` - The SVG document or group to modify
 /// * `layer_style` - The style to apply to the features
+/// * `labels` - The placer collecting the labels of the features
+/// * `defined` - The ids of the definitions already in the document
 ///
-/// # Panics
-/// Panics if there is an error reading data from the shapefile or if the 'name' field is missing in a record.
-pub fn visualize_shapefile(
+/// Features without a label text in any of the label languages are drawn without a label.
+///
+/// Returns the number of features skipped for lack of a drawable geometry.
+pub fn visualize_features<'a>(
     map: &Map,
-    mut reader: Reader<BufReader<File>, BufReader<File>>,
-    document: &mut Document,
-    layer_style: &LayerStyle,
-) {
-    for result in reader.iter_shapes_and_records() {
-        let (shape, record) = result.expect("Error reading data from shapefile");
+    features: impl IntoIterator<Item = (Shape, Record)>,
+    document: &mut impl Node,
+    layer_style: &LayerStyle<'a>,
+    labels: &mut LabelPlacer<'a>,
+    defined: &mut DefinedIds,
+) -> usize {
+    append_layer_defs(document, layer_style, defined);
 
- 
-        match shape {
-            Shape::Point(point) => {
-                let name = record.get("name").expect("'name' field missing in record");
+    let mut skipped = 0;
+    let features = features.into_iter().filter_map(|(shape, record)| {
+        if layer_style
+            .feature_filter
+            .as_ref()
+            .is_some_and(|filter| !attributes::matches(&record, filter))
+        {
+            return None;
+        }
+        let flat = flatten(shape);
+        if flat.is_none() {
+            skipped += 1;
+        }
+        flat.map(|shape| (shape, record))
+    });
+
+    // The layer is drawn as one group, so filters and blending apply to it as a whole
+    let mut group = element::Group::new();
+    if let Some(filter) = &layer_style.filter {
+        group.assign("filter", filter_url(filter));
+    }
+    if let Some(mix_blend_mode) = layer_style.mix_blend_mode {
+        group.assign("style", format!("mix-blend-mode:{mix_blend_mode}"));
+    }
 
-                let label = if let shapefile::dbase::FieldValue::Character(Some(label)) = name {
-                    label
-                } else {
-                    ""
-                };
+    // Water lines go underneath all features of the layer, so they need the whole layer at once
+    let mut features_group = element::Group::new();
+    let mut pass_groups = stroke_pass_groups(&layer_style.stroke_passes);
+    if let Some(water_lines) = &layer_style.water_lines {
+        let features: Vec<(Shape, Record)> = features.collect();
+        draw_water_lines(&outer_rings(&features, map), &mut group, water_lines);
+        draw_features(
+            features,
+            map,
+            &mut features_group,
+            &mut pass_groups,
+            labels,
+            layer_style,
+        );
+    } else {
+        draw_features(
+            features,
+            map,
+            &mut features_group,
+            &mut pass_groups,
+            labels,
+            layer_style,
+        );
+    }
+
+    // The stroke passes of all features go beneath the main strokes of all features
+    for pass_group in pass_groups {
+        group.append(pass_group);
+    }
+    if let Some(features) = features_group.get_children_mut() {
+        for feature in features.drain(..) {
+            group.append(feature);
+        }
+    }
+    document.append(group);
+    skipped
+}
 
-                point_fn(&point, label, map, document, layer_style);
+/// Draws flattened features into the group of their layer and adds their labels
+///
+/// The outlines of the polylines and polygon rings are added to the stroke pass groups too.
+fn draw_features<'a>(
+    features: impl IntoIterator<Item = (Shape, Record)>,
+    map: &Map,
+    group: &mut element::Group,
+    pass_groups: &mut [element::Group],
+    labels: &mut LabelPlacer<'a>,
+    layer_style: &LayerStyle<'a>,
+) {
+    for (shape, record) in features {
+        match shape {
+            Shape::Point(point) => {
+                point_fn(&[point], &record, map, group, labels, layer_style);
             }
             Shape::Multipoint(multi_point) => {
-                for point in multi_point.points() {
-                    println!("MPoint: {point:?}");
-                }
+                point_fn(
+                    multi_point.points(),
+                    &record,
+                    map,
+                    group,
+                    labels,
+                    layer_style,
+                );
             }
             Shape::Polygon(polygon) => {
                 for ring in polygon.rings() {
-                    polygon_fn(ring.points(), map, document, layer_style);
+                    polygon_fn(ring.points(), map, group, pass_groups, layer_style);
                 }
+                polygon_label_fn(polygon, &record, map, labels, layer_style);
             }
 
             Shape::Polyline(polyline) => {
                 for part in polyline.parts() {
-                    polyline_fn(part, map, document, layer_style);
+                    polyline_fn(part, map, group, pass_groups, layer_style);
                 }
+                line_labels_fn(polyline.parts(), &record, map, labels, layer_style);
             }
+            // All other shapes were flattened or skipped above
             _ => {}
         }
     }
 }
 
-fn point_fn(point: &Point, label: &str, map: &Map, document: &mut Document, layer_style: &LayerStyle) {
-    let pt = mapping_function(point.x, point.y, map);
-    draw_text(pt, label, document, 12, layer_style.fill);
+/// Converts a shape to one of the two-dimensional shapes that are drawn, dropping z and m values
+///
+/// Multipatches become polygons, with every triangle of a strip or fan as a ring of its own.
+/// Returns `None` for a null shape or a multipatch without a patch of at least three points.
+fn flatten(shape: Shape) -> Option<Shape> {
+    let flat = match shape {
+        Shape::PointM(point) => Shape::Point(Point::new(point.x, point.y)),
+        Shape::PointZ(point) => Shape::Point(Point::new(point.x, point.y)),
+        Shape::MultipointM(multi_point) => {
+            Shape::Multipoint(Multipoint::new(points_2d(multi_point.points())))
+        }
+        Shape::MultipointZ(multi_point) => {
+            Shape::Multipoint(Multipoint::new(points_2d(multi_point.points())))
+        }
+        Shape::PolylineM(polyline) => Shape::Polyline(Polyline::with_parts(
+            polyline
+                .parts()
+                .iter()
+                .map(|part| points_2d(part))
+                .collect(),
+        )),
+        Shape::PolylineZ(polyline) => Shape::Polyline(Polyline::with_parts(
+            polyline
+                .parts()
+                .iter()
+                .map(|part| points_2d(part))
+                .collect(),
+        )),
+        Shape::PolygonM(polygon) => Shape::Polygon(Polygon::with_rings(
+            polygon.rings().iter().map(ring_2d).collect(),
+        )),
+        Shape::PolygonZ(polygon) => Shape::Polygon(Polygon::with_rings(
+            polygon.rings().iter().map(ring_2d).collect(),
+        )),
+        Shape::Multipatch(multipatch) => Shape::Polygon(multipatch_polygon(&multipatch)?),
+        Shape::NullShape => return None,
+        shape => shape,
+    };
+    Some(flat)
 }
 
+/// Returns the rings of a multipatch as a polygon, `None` if none of them can be drawn
+///
+/// Rings of unknown type count as outer rings.
+fn multipatch_polygon(multipatch: &Multipatch) -> Option<Polygon> {
+    let mut rings = Vec::new();
+    for patch in multipatch.patches() {
+        match patch {
+            Patch::TriangleStrip(points) => {
+                for triangle in points.windows(3) {
+                    rings.push(PolygonRing::Outer(points_2d(triangle)));
+                }
+            }
+            Patch::TriangleFan(points) => {
+                for pair in points.get(1..).unwrap_or_default().windows(2) {
+                    rings.push(PolygonRing::Outer(points_2d(&[
+                        points[0], pair[0], pair[1],
+                    ])));
+                }
+            }
+            Patch::OuterRing(points) | Patch::FirstRing(points) | Patch::Ring(points) => {
+                if points.len() >= 3 {
+                    rings.push(PolygonRing::Outer(points_2d(points)));
+                }
+            }
+            Patch::InnerRing(points) => {
+                if points.len() >= 3 {
+                    rings.push(PolygonRing::Inner(points_2d(points)));
+                }
+            }
+        }
+    }
+    if rings.is_empty() {
+        None
+    } else {
+        Some(Polygon::with_rings(rings))
+    }
+}
 
-fn polyline_fn(part: &[Point], map: &Map, document: &mut Document, layer_style: &LayerStyle) {
-    let data = element::path::Data::new();
-    let pts: Vec<_> = part
+/// Converts a polygon ring with z or m values to a two-dimensional ring
+fn ring_2d<P: HasXY>(ring: &PolygonRing<P>) -> PolygonRing<Point> {
+    match ring {
+        PolygonRing::Outer(points) => PolygonRing::Outer(points_2d(points)),
+        PolygonRing::Inner(points) => PolygonRing::Inner(points_2d(points)),
+    }
+}
+
+/// Converts points with z or m values to two-dimensional points
+fn points_2d<P: HasXY>(points: &[P]) -> Vec<Point> {
+    points
         .iter()
-        .map(|point| mapping_function(point.x, point.y, map))
+        .map(|point| Point::new(point.x(), point.y()))
+        .collect()
+}
+
+/// Draws the markers of a point feature and adds its label at the first point
+///
+/// Markers are kept clear of labels, so labels of marked points move beside them.
+fn point_fn<'a>(
+    points: &[Point],
+    record: &Record,
+    map: &Map,
+    document: &mut impl Node,
+    labels: &mut LabelPlacer<'a>,
+    layer_style: &LayerStyle<'a>,
+) {
+    let positions = project(points, map);
+
+    if let Some(marker) = &layer_style.marker {
+        let size = marker
+            .size_field
+            .and_then(|field| attributes::number(record, field))
+            .map_or(marker.size, |value| {
+                marker.size_scale * value.max(0.0).sqrt()
+            });
+        for position in &positions {
+            draw_marker(*position, size, document, marker);
+            labels.reserve(*position, size, size);
+        }
+    }
+
+    let (Some(label), Some(anchor)) = (label_text(record, &layer_style.label), positions.first())
+    else {
+        return;
+    };
+    labels.add(Label {
+        text: label.to_string(),
+        anchor: *anchor,
+        priority: label_priority(record, layer_style),
+        style: layer_style.label.clone(),
+        fill: label_fill(&layer_style.label, layer_style.fill),
+        movable: true,
+    });
+}
+
+/// Adds a label along each part of a line feature, if the style labels lines
+fn line_labels_fn<'a>(
+    parts: &[Vec<Point>],
+    record: &Record,
+    map: &Map,
+    labels: &mut LabelPlacer<'a>,
+    layer_style: &LayerStyle<'a>,
+) {
+    if layer_style.label.line_spacing.is_none() {
+        return;
+    }
+    let Some(name) = label_text(record, &layer_style.label) else {
+        return;
+    };
+    for part in parts {
+        labels.add_line(LineLabel {
+            text: name.to_string(),
+            points: project(part, map),
+            priority: label_priority(record, layer_style),
+            style: layer_style.label.clone(),
+            fill: label_fill(&layer_style.label, layer_style.stroke),
+        });
+    }
+}
+
+/// Adds a label at the visual center of the largest part of a polygon feature,
+/// if the style labels polygons and the part is large enough on screen
+fn polygon_label_fn<'a>(
+    polygon: shapefile::Polygon,
+    record: &Record,
+    map: &Map,
+    labels: &mut LabelPlacer<'a>,
+    layer_style: &LayerStyle<'a>,
+) {
+    let style = &layer_style.label;
+    if !style.polygon_labels {
+        return;
+    }
+    let Some(name) = label_text(record, style) else {
+        return;
+    };
+
+    let projected = MultiPolygon::<f64>::from(polygon).map_coords(|coord| {
+        let (x, y) = mapping_function(coord.x, coord.y, map);
+        Coord { x, y }
+    });
+    let Some(largest) = projected
+        .iter()
+        .max_by(|a, b| a.unsigned_area().total_cmp(&b.unsigned_area()))
+    else {
+        return;
+    };
+    let area = largest.unsigned_area();
+    if area < style.min_area {
+        return;
+    }
+    let Some(center) = polylabel(largest, POLYLABEL_PRECISION) else {
+        return;
+    };
+
+    let mut label_style = style.clone();
+    if let Some(max_font_size) = style.max_font_size {
+        label_style.font_size = (area.sqrt() * AREA_FONT_RATIO)
+            .max(style.font_size)
+            .min(max_font_size);
+    }
+    labels.add(Label {
+        text: name.to_string(),
+        anchor: (center.x, center.y),
+        priority: label_priority(record, layer_style),
+        style: label_style,
+        fill: label_fill(style, layer_style.stroke),
+        movable: false,
+    });
+}
+
+/// Returns the label text of a feature in the first available preferred language,
+/// `None` if the feature has no text in any of them nor in the label field itself
+fn label_text<'r>(record: &'r Record, label_style: &LabelStyle) -> Option<&'r str> {
+    let localized: Vec<String> = label_style
+        .languages
+        .iter()
+        .map(|language| format!("{}_{language}", label_style.field))
         .collect();
+    attributes::first_text(
+        record,
+        localized
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(label_style.field)),
+    )
+}
+
+/// Returns the text color of a label, the color of its feature if the label style has none,
+/// black if the feature has no color either
+fn label_fill<'a>(label_style: &LabelStyle<'a>, feature_color: &'a str) -> &'a str {
+    label_style.fill.unwrap_or(if feature_color == "none" {
+        DEFAULT_LABEL_FILL
+    } else {
+        feature_color
+    })
+}
+
+/// Returns the label priority of a feature, features without one are placed last
+fn label_priority(record: &Record, layer_style: &LayerStyle) -> f64 {
+    layer_style
+        .label
+        .priority_field
+        .and_then(|field| attributes::number(record, field))
+        .unwrap_or(f64::INFINITY)
+}
+
+/// Returns the outer rings of all polygon features in pixel coordinates
+fn outer_rings(features: &[(Shape, Record)], map: &Map) -> Vec<Vec<(f64, f64)>> {
+    let mut rings = Vec::new();
+    for (shape, _) in features {
+        if let Shape::Polygon(polygon) = shape {
+            for ring in polygon.rings() {
+                if let PolygonRing::Outer(points) = ring {
+                    rings.push(project(points, map));
+                }
+            }
+        }
+    }
+    rings
+}
+
+/// Projects shapefile points to pixel coordinates
+fn project(points: &[Point], map: &Map) -> Vec<(f64, f64)> {
+    points
+        .iter()
+        .map(|point| mapping_function(point.x, point.y, map))
+        .collect()
+}
+
+fn polyline_fn(
+    part: &[Point],
+    map: &Map,
+    document: &mut impl Node,
+    pass_groups: &mut [element::Group],
+    layer_style: &LayerStyle,
+) {
+    let data = element::path::Data::new();
+    let pts = project(part, map);
 
     // TODO Enabling this will clip polylines outside the map, reducing file size
     /*
@@ -94,15 +502,19 @@ fn polyline_fn(part: &[Point], map: &Map, document: &mut Document, layer_style:
         continue;
     }
     */
+    add_to_stroke_passes(pass_groups, &pts);
     draw_polyline(&pts, document, layer_style, data);
 }
 
-fn polygon_fn(ring: &[Point], map: &Map, document: &mut Document, layer_style: &LayerStyle) {
+fn polygon_fn(
+    ring: &[Point],
+    map: &Map,
+    document: &mut impl Node,
+    pass_groups: &mut [element::Group],
+    layer_style: &LayerStyle,
+) {
     let data = element::path::Data::new();
-    let pts: Vec<_> = ring
-        .iter()
-        .map(|point| mapping_function(point.x, point.y, map))
-        .collect();
+    let pts = project(ring, map);
 
     // TODO Enabling this will clip polygons outside the map, reducing file size
     // TODO account for cases like the ocean, which are completely outside the map
@@ -110,5 +522,6 @@ fn polygon_fn(ring: &[Point], map: &Map, document: &mut Document, layer_style: &
     //if !pts.iter().all(|pt| pt.0 >= 0.0 && pt.0 <= map.cols as f64 && pt.1 >= 0.0 && pt.1 <= map.rows as f64) {
     //    continue;
     //}
+    add_to_stroke_passes(pass_groups, &pts);
     draw_polygon(&pts, document, layer_style, data);
 }
@@ -0,0 +1,440 @@
+//! This module provides functionality for reading the features of layers from their data files.
+//!
+//! Layers are read from shapefiles, zipped shapefiles, `GeoJSON` files or tables of a `GeoPackage`,
+//! chosen by the file extension. Each format but plain shapefiles needs its cargo feature,
+//! all enabled by default.
+//! Whatever the source, the features are converted to shapes with their attributes in a record,
+//! so they are drawn and labeled the same way.
+
+use std::fs::File;
+#[cfg(feature = "zip")]
+use std::io::Cursor;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+#[cfg(any(feature = "geojson", feature = "geopackage"))]
+use geo::Geometry;
+#[cfg(feature = "geojson")]
+use geojson::{GeoJson, GeometryValue, JsonValue, Position};
+#[cfg(feature = "geojson")]
+use shapefile::dbase::FieldValue;
+use shapefile::dbase::Record;
+use shapefile::{Reader, Shape};
+#[cfg(feature = "zip")]
+use shapefile::{ShapeReader, dbase};
+#[cfg(feature = "zip")]
+use zip::ZipArchive;
+
+#[cfg(feature = "geopackage")]
+use super::geopackage::geopackage_features;
+#[cfg(any(feature = "geojson", feature = "geopackage"))]
+use super::overlay::geometry_shapes;
+
+/// Reads the features of a layer from a shapefile, zip archive, `GeoJSON` file or `GeoPackage` table
+///
+/// Files ending in `.geojson` or `.json` are read as `GeoJSON`, files ending in `.zip`
+/// as a shapefile in the archive, all others as shapefiles.
+/// A table of a `GeoPackage` is given after a `#`, e.g. `natural_earth_vector.gpkg#ne_10m_land`,
+/// as is the shapefile of an archive holding several, e.g. `ne_10m_physical.zip#ne_10m_land`.
+/// Any other `#` in the path is part of a file or directory name.
+///
+/// # Panics
+/// Panics if the file cannot be read or parsed, a `GeoPackage` is given without a table,
+/// or the feature of its format is disabled.
+#[must_use]
+pub fn read_features(path: &Path) -> Vec<(Shape, Record)> {
+    visit_features(path, |features| features.collect())
+}
+
+/// Passes the features of a layer to a function as they are read, see [`read_features`]
+///
+/// Shapefiles are read one feature at a time, so a layer need not be held in memory.
+/// The members of a zipped shapefile are read into memory whole, but converted one feature at a time.
+/// `GeoJSON` files and `GeoPackage` tables are read whole before their features are passed on.
+///
+/// # Panics
+/// Panics if the file cannot be read or parsed, a `GeoPackage` is given without a table,
+/// or the feature of its format is disabled.
+pub fn visit_features<R>(
+    path: &Path,
+    visit: impl FnOnce(&mut dyn Iterator<Item = (Shape, Record)>) -> R,
+) -> R {
+    match source(path) {
+        Source::GeoPackage(_, None) => {
+            panic!("No table given for GeoPackage: {}", path.display())
+        }
+        #[cfg(feature = "geopackage")]
+        Source::GeoPackage(file, Some(table)) => {
+            visit(&mut geometry_features(geopackage_features(file, table)))
+        }
+        #[cfg(not(feature = "geopackage"))]
+        Source::GeoPackage(..) => disabled("geopackage", path),
+        #[cfg(feature = "zip")]
+        Source::Zip(file, member) => visit(&mut shape_records(&mut zipped_shapefile_reader(
+            file, member,
+        ))),
+        #[cfg(not(feature = "zip"))]
+        Source::Zip(..) => disabled("zip", path),
+        #[cfg(feature = "geojson")]
+        Source::GeoJson(file) => visit(&mut geometry_features(geojson_features(file))),
+        #[cfg(not(feature = "geojson"))]
+        Source::GeoJson(_) => disabled("geojson", path),
+        Source::Shapefile(file) => visit(&mut shape_records(&mut shapefile_reader(file))),
+    }
+}
+
+/// Panics for a layer whose format needs a disabled cargo feature
+#[cfg(not(all(feature = "geojson", feature = "geopackage", feature = "zip")))]
+fn disabled(feature: &str, path: &Path) -> ! {
+    panic!(
+        "Reading {} needs the {feature} feature of natural-earth-basemap",
+        path.display()
+    )
+}
+
+/// The kind of file a layer is read from, with the table or shapefile given after a `#`
+#[derive(PartialEq, Eq, Debug)]
+enum Source<'p> {
+    GeoPackage(&'p Path, Option<&'p str>),
+    Zip(&'p Path, Option<&'p str>),
+    GeoJson(&'p Path),
+    Shapefile(&'p Path),
+}
+
+/// Returns the kind of file of a layer path
+///
+/// The path is only split at its last `#` if the part before it is a `GeoPackage` or zip archive,
+/// so a `#` elsewhere, e.g. in a directory name, is part of the file name.
+fn source(path: &Path) -> Source<'_> {
+    if let Some((file, member)) = path.to_str().and_then(|path| path.rsplit_once('#')) {
+        let file = Path::new(file);
+        match extension(file).as_deref() {
+            Some("gpkg") => return Source::GeoPackage(file, Some(member)),
+            Some("zip") => return Source::Zip(file, Some(member)),
+            _ => {}
+        }
+    }
+    match extension(path).as_deref() {
+        Some("gpkg") => Source::GeoPackage(path, None),
+        Some("zip") => Source::Zip(path, None),
+        Some("geojson" | "json") => Source::GeoJson(path),
+        _ => Source::Shapefile(path),
+    }
+}
+
+/// Opens a shapefile
+fn shapefile_reader(path: &Path) -> Reader<BufReader<File>, BufReader<File>> {
+    Reader::from_path(path)
+        .unwrap_or_else(|_| panic!("Error loading shapefile: {}", path.display()))
+}
+
+/// Returns the shapes of a shapefile with their records as they are read
+fn shape_records<T: Read + Seek, D: Read + Seek>(
+    reader: &mut Reader<T, D>,
+) -> impl Iterator<Item = (Shape, Record)> + '_ {
+    reader
+        .iter_shapes_and_records()
+        .map(|result| result.expect("Error reading data from shapefile"))
+}
+
+#[cfg(feature = "zip")]
+/// Opens a shapefile in a zip archive without extracting it, with its members read into memory
+///
+/// The shapefile is given by its name without extension, e.g. `ne_10m_land`,
+/// and may be left out if the archive holds a single one. Its `.shp` and `.dbf` members are read,
+/// as is its `.shx` member if there is one.
+fn zipped_shapefile_reader(
+    path: &Path,
+    shapefile: Option<&str>,
+) -> Reader<Cursor<Vec<u8>>, Cursor<Vec<u8>>> {
+    let file = File::open(path)
+        .unwrap_or_else(|_| panic!("Error loading zip archive: {}", path.display()));
+    let mut archive = ZipArchive::new(file)
+        .unwrap_or_else(|error| panic!("Error reading zip archive {}: {error}", path.display()));
+
+    let stems: Vec<String> = archive
+        .file_names()
+        .filter_map(|name| {
+            let (stem, extension) = name.rsplit_once('.')?;
+            extension
+                .eq_ignore_ascii_case("shp")
+                .then(|| stem.to_string())
+        })
+        .filter(|stem| {
+            shapefile.is_none_or(|shapefile| {
+                stem.rsplit('/').next() == Some(shapefile) || stem == shapefile
+            })
+        })
+        .collect();
+    let [stem] = stems.as_slice() else {
+        match shapefile {
+            Some(shapefile) => panic!(
+                "No shapefile {shapefile} in zip archive: {}",
+                path.display()
+            ),
+            None => panic!(
+                "Expected one shapefile in zip archive, found {}: {}",
+                stems.len(),
+                path.display()
+            ),
+        }
+    };
+
+    let shp = zipped_member(&mut archive, stem, "shp")
+        .unwrap_or_else(|| panic!("Error reading {stem}.shp from zip archive"));
+    let dbf = zipped_member(&mut archive, stem, "dbf")
+        .unwrap_or_else(|| panic!("Missing {stem}.dbf in zip archive: {}", path.display()));
+    let shape_reader = match zipped_member(&mut archive, stem, "shx") {
+        Some(shx) => ShapeReader::with_shx(Cursor::new(shp), Cursor::new(shx)),
+        None => ShapeReader::new(Cursor::new(shp)),
+    }
+    .unwrap_or_else(|_| panic!("Error loading shapefile {stem} from zip archive"));
+    let dbase_reader = dbase::Reader::new(Cursor::new(dbf))
+        .unwrap_or_else(|_| panic!("Error loading attributes of {stem} from zip archive"));
+
+    Reader::new(shape_reader, dbase_reader)
+}
+
+#[cfg(feature = "zip")]
+/// Returns the contents of the member of a zip archive with a name and extension,
+/// ignoring the case of the extension, `None` if there is no such member
+fn zipped_member(archive: &mut ZipArchive<File>, stem: &str, extension: &str) -> Option<Vec<u8>> {
+    let name = archive
+        .file_names()
+        .find(|name| {
+            name.strip_prefix(stem)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .is_some_and(|rest| rest.eq_ignore_ascii_case(extension))
+        })?
+        .to_string();
+    let mut member = archive.by_name(&name).ok()?;
+    let mut bytes = Vec::new();
+    member.read_to_end(&mut bytes).ok()?;
+    Some(bytes)
+}
+
+/// Reads the features of a `GeoJSON` file with their properties as records
+///
+/// The file may hold a feature collection, a single feature or a bare geometry.
+/// Features without a geometry are left out. Strings, numbers and booleans become
+/// character, numeric and logical fields, nested arrays and objects are kept as JSON text.
+///
+/// Returns the features with the number of features skipped for being malformed,
+/// like a position of a single coordinate.
+///
+/// # Panics
+/// Panics if the file cannot be read or is no valid `GeoJSON`.
+#[cfg(feature = "geojson")]
+#[must_use]
+pub fn read_geojson(path: &Path) -> (Vec<(Geometry<f64>, Record)>, usize) {
+    let mut skipped = 0;
+    let features = geojson_features(path)
+        .into_iter()
+        .filter_map(|(geometry, record)| {
+            if geometry.is_none() {
+                skipped += 1;
+            }
+            Some((geometry?, record))
+        })
+        .collect();
+    (features, skipped)
+}
+
+#[cfg(feature = "geojson")]
+/// Reads the features of a `GeoJSON` file, with `None` for a malformed geometry
+fn geojson_features(path: &Path) -> Vec<(Option<Geometry<f64>>, Record)> {
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|_| panic!("Error loading GeoJSON: {}", path.display()));
+    let mut json: JsonValue = text
+        .parse()
+        .unwrap_or_else(|error| panic!("Error parsing GeoJSON {}: {error}", path.display()));
+
+    // The members of a collection are converted one by one, so a malformed one is skipped alone
+    let kind = json
+        .get("type")
+        .and_then(JsonValue::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let members = match kind.as_str() {
+        "FeatureCollection" => match json.get_mut("features").map(JsonValue::take) {
+            Some(JsonValue::Array(features)) => features,
+            _ => panic!("No features in GeoJSON: {}", path.display()),
+        },
+        "Feature" | "Point" | "MultiPoint" | "LineString" | "MultiLineString" | "Polygon"
+        | "MultiPolygon" | "GeometryCollection" => vec![json],
+        _ => panic!("Error parsing GeoJSON {}: no GeoJSON type", path.display()),
+    };
+
+    members
+        .into_iter()
+        .filter_map(|member| match serde_json::from_value::<GeoJson>(member) {
+            Ok(GeoJson::Feature(feature)) => {
+                let geometry = convert_geometry(feature.geometry?);
+                let mut record = Record::default();
+                for (key, value) in feature.properties.into_iter().flatten() {
+                    record.insert(key, field_value(value));
+                }
+                Some((geometry, record))
+            }
+            Ok(GeoJson::Geometry(geometry)) => {
+                Some((convert_geometry(geometry), Record::default()))
+            }
+            Ok(GeoJson::FeatureCollection(_)) | Err(_) => Some((None, Record::default())),
+        })
+        .collect()
+}
+
+#[cfg(feature = "geojson")]
+/// Converts a `GeoJSON` geometry, `None` if it is malformed
+fn convert_geometry(geometry: geojson::Geometry) -> Option<Geometry<f64>> {
+    // Only the positions of points are checked when parsing, and the conversion panics
+    // on a position of less than two coordinates
+    if !valid_positions(&geometry.value) {
+        return None;
+    }
+    Geometry::try_from(geometry).ok()
+}
+
+#[cfg(feature = "geojson")]
+/// Returns whether all positions of a `GeoJSON` geometry have an x and a y coordinate
+fn valid_positions(value: &GeometryValue) -> bool {
+    let valid = |position: &Position| position.as_slice().len() >= 2;
+    match value {
+        GeometryValue::Point { coordinates } => valid(coordinates),
+        GeometryValue::MultiPoint { coordinates } | GeometryValue::LineString { coordinates } => {
+            coordinates.iter().all(valid)
+        }
+        GeometryValue::MultiLineString { coordinates } | GeometryValue::Polygon { coordinates } => {
+            coordinates.iter().flatten().all(valid)
+        }
+        GeometryValue::MultiPolygon { coordinates } => {
+            coordinates.iter().flatten().flatten().all(valid)
+        }
+        GeometryValue::GeometryCollection { geometries } => geometries
+            .iter()
+            .all(|geometry| valid_positions(&geometry.value)),
+    }
+}
+
+#[cfg(any(feature = "geojson", feature = "geopackage"))]
+/// Converts geometries to shapes, the shapes of a feature sharing its record
+///
+/// A feature without a valid geometry becomes a null shape, so it is counted as skipped when drawn.
+fn geometry_features(
+    features: impl IntoIterator<Item = (Option<Geometry<f64>>, Record)>,
+) -> impl Iterator<Item = (Shape, Record)> {
+    features.into_iter().flat_map(|(geometry, record)| {
+        let mut shapes = geometry.map(geometry_shapes).unwrap_or_default();
+        if shapes.is_empty() {
+            shapes.push(Shape::NullShape);
+        }
+        shapes.into_iter().map(move |shape| (shape, record.clone()))
+    })
+}
+
+#[cfg(feature = "geojson")]
+/// Converts a JSON property value to a field value
+fn field_value(value: JsonValue) -> FieldValue {
+    match value {
+        JsonValue::Null => FieldValue::Character(None),
+        JsonValue::Bool(value) => FieldValue::Logical(Some(value)),
+        JsonValue::Number(number) => FieldValue::Numeric(number.as_f64()),
+        JsonValue::String(text) => FieldValue::Character(Some(text)),
+        value @ (JsonValue::Array(_) | JsonValue::Object(_)) => {
+            FieldValue::Character(Some(value.to_string()))
+        }
+    }
+}
+
+/// Returns the lower case extension of a file
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_tables_and_archive_members() {
+        assert_eq!(
+            source(Path::new("data/ne.gpkg#ne_10m_land")),
+            Source::GeoPackage(Path::new("data/ne.gpkg"), Some("ne_10m_land"))
+        );
+        assert_eq!(
+            source(Path::new("data/NE.ZIP#ne_10m_land")),
+            Source::Zip(Path::new("data/NE.ZIP"), Some("ne_10m_land"))
+        );
+        assert_eq!(
+            source(Path::new("runs#2/ne.zip#ne_10m_land")),
+            Source::Zip(Path::new("runs#2/ne.zip"), Some("ne_10m_land"))
+        );
+    }
+
+    #[test]
+    fn keeps_other_hashes_in_the_path() {
+        assert_eq!(
+            source(Path::new("data#1/x.geojson")),
+            Source::GeoJson(Path::new("data#1/x.geojson"))
+        );
+        assert_eq!(
+            source(Path::new("runs#2/ne_10m_land.shp")),
+            Source::Shapefile(Path::new("runs#2/ne_10m_land.shp"))
+        );
+        assert_eq!(
+            source(Path::new("runs#2/ne.zip")),
+            Source::Zip(Path::new("runs#2/ne.zip"), None)
+        );
+        assert_eq!(
+            source(Path::new("ne.gpkg")),
+            Source::GeoPackage(Path::new("ne.gpkg"), None)
+        );
+    }
+
+    #[test]
+    fn routes_by_extension() {
+        assert_eq!(
+            source(Path::new("points.JSON")),
+            Source::GeoJson(Path::new("points.JSON"))
+        );
+        assert_eq!(
+            source(Path::new("ne_10m_land")),
+            Source::Shapefile(Path::new("ne_10m_land"))
+        );
+    }
+
+    #[cfg(feature = "geojson")]
+    #[test]
+    fn reads_geojson_from_a_directory_with_a_hash_skipping_malformed_features() {
+        let directory = std::env::temp_dir().join(format!("basemap#{}", std::process::id()));
+        std::fs::create_dir_all(&directory).expect("Error creating test directory");
+        let path = directory.join("points.geojson");
+        std::fs::write(
+            &path,
+            r#"{"type": "FeatureCollection", "features": [
+                {"type": "Feature", "properties": {"name": "A"},
+                 "geometry": {"type": "Point", "coordinates": [1.0, 2.0]}},
+                {"type": "Feature", "properties": {"name": "B"},
+                 "geometry": {"type": "Point", "coordinates": [3.0]}},
+                {"type": "Feature", "properties": {"name": "C"},
+                 "geometry": {"type": "LineString", "coordinates": [[3.0, 4.0], [5.0]]}}
+            ]}"#,
+        )
+        .expect("Error writing test GeoJSON");
+
+        let features = read_features(&path);
+        let (geojson, skipped) = read_geojson(&path);
+        std::fs::remove_dir_all(&directory).expect("Error removing test directory");
+
+        // The malformed features become null shapes, so they are counted as skipped when drawn
+        assert_eq!(features.len(), 3);
+        assert!(matches!(features[1].0, Shape::NullShape));
+        assert!(matches!(features[2].0, Shape::NullShape));
+        assert_eq!((geojson.len(), skipped), (1, 2));
+        assert!(
+            matches!(features[0].0, Shape::Point(point) if point == shapefile::Point::new(1.0, 2.0))
+        );
+    }
+}
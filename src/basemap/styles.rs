@@ -3,7 +3,312 @@
 //! This module provides various map styles that can be used to render the Natural Earth basemap.
 //! Each style defines how different geographic features (land, ocean, lakes, etc.) should be displayed.
 
+/// A single stroke pass drawn beneath the main stroke of a layer
+///
+/// Stacking passes allows casings, e.g. a wide dark pass underneath a
+/// narrower light main stroke for roads or boundaries.
+#[derive(Clone)]
+pub struct StrokePass<'a> {
+    /// The stroke color of the pass
+    /// (e.g. "black", "#000000")
+    pub stroke: &'a str,
+    /// The stroke width of the pass in pixels
+    /// (e.g. "3" for 3 pixel width)
+    pub stroke_width: &'a str,
+    /// The dash pattern of the pass, `None` for a solid line
+    /// (e.g. "4 2" for 4 pixels on, 2 pixels off)
+    pub stroke_dasharray: Option<&'a str>,
+    /// The shape at the ends of open lines, `None` for the SVG default
+    /// (e.g. "butt", "round", "square")
+    pub stroke_linecap: Option<&'a str>,
+    /// The shape at the corners of lines, `None` for the SVG default
+    /// (e.g. "miter", "round", "bevel")
+    pub stroke_linejoin: Option<&'a str>,
+}
+
+/// A pattern used to fill polygons, drawn on top of the fill color
+#[derive(Clone)]
+pub enum FillPattern<'a> {
+    /// Parallel lines
+    Hatch {
+        /// The angle of the lines in degrees, clockwise from horizontal
+        angle: f64,
+        /// The distance between neighbouring lines in pixels
+        spacing: f64,
+        /// The color of the lines
+        /// (e.g. "black", "#000000")
+        stroke: &'a str,
+        /// The width of the lines in pixels
+        stroke_width: f64,
+    },
+    /// Dots on a regular grid (stipple)
+    Dots {
+        /// The distance between neighbouring dots in pixels
+        spacing: f64,
+        /// The radius of the dots in pixels
+        radius: f64,
+        /// The color of the dots
+        /// (e.g. "black", "#000000")
+        fill: &'a str,
+    },
+    /// A custom tile of SVG content repeated across the polygon
+    Tile {
+        /// The SVG markup of the tile
+        /// (e.g. `<path d="M0 4 L4 0" stroke="blue"/>`)
+        content: &'a str,
+        /// The width of the tile in pixels
+        width: f64,
+        /// The height of the tile in pixels
+        height: f64,
+    },
+}
+
+/// A color stop of a gradient
+#[derive(Clone)]
+pub struct GradientStop<'a> {
+    /// The position of the stop along the gradient (0.0 to 1.0)
+    pub offset: f64,
+    /// The color at the stop
+    /// (e.g. "navy", "#023858")
+    pub color: &'a str,
+    /// The opacity at the stop (0.0 to 1.0)
+    pub opacity: f64,
+}
+
+/// A gradient used to fill a layer instead of a flat color
+///
+/// Coordinates are fractions (0.0 to 1.0) of the bounding box of the filled shape.
+#[derive(Clone)]
+pub enum Gradient<'a> {
+    /// A gradient along the line from (`x1`, `y1`) to (`x2`, `y2`)
+    Linear {
+        /// The horizontal start of the gradient
+        x1: f64,
+        /// The vertical start of the gradient
+        y1: f64,
+        /// The horizontal end of the gradient
+        x2: f64,
+        /// The vertical end of the gradient
+        y2: f64,
+        /// The color stops, ordered by offset
+        stops: Vec<GradientStop<'a>>,
+    },
+    /// A gradient radiating from the center (`cx`, `cy`) out to the radius `r`
+    Radial {
+        /// The horizontal center of the gradient
+        cx: f64,
+        /// The vertical center of the gradient
+        cy: f64,
+        /// The radius of the gradient
+        r: f64,
+        /// The color stops, ordered by offset
+        stops: Vec<GradientStop<'a>>,
+    },
+}
+
+/// Concentric "water lines" drawn around polygons, fading with distance
+///
+/// Each line follows the polygon outlines buffered outward by one of the distances.
+#[derive(Clone)]
+pub struct WaterLines<'a> {
+    /// The distances of the lines from the polygon outlines in pixels, nearest first
+    /// (e.g. `vec![3.0, 7.0, 12.0, 18.0]`)
+    pub distances: Vec<f64>,
+    /// The color of the lines
+    /// (e.g. "steelblue", "#4682B4")
+    pub stroke: &'a str,
+    /// The width of the lines in pixels
+    pub stroke_width: f64,
+    /// The opacity of the nearest line (0.0 to 1.0), lines further out fade toward zero
+    pub opacity: f64,
+}
+
+/// An SVG filter effect applied to a whole layer
+#[derive(Clone)]
+pub enum Filter<'a> {
+    /// A blurred, offset copy of the layer drawn beneath it
+    DropShadow {
+        /// The horizontal offset of the shadow in pixels
+        dx: f64,
+        /// The vertical offset of the shadow in pixels
+        dy: f64,
+        /// The standard deviation of the shadow blur in pixels
+        blur: f64,
+        /// The color of the shadow
+        /// (e.g. "black", "#000000")
+        color: &'a str,
+        /// The opacity of the shadow (0.0 to 1.0)
+        opacity: f64,
+    },
+    /// A Gaussian blur of the layer
+    Blur {
+        /// The standard deviation of the blur in pixels
+        std_deviation: f64,
+    },
+}
+
+/// The shape of a point marker
+#[derive(Clone)]
+pub enum MarkerShape<'a> {
+    /// A circle
+    Circle,
+    /// A square
+    Square,
+    /// An upward pointing equilateral triangle
+    Triangle,
+    /// A five pointed star
+    Star,
+    /// An external image, typically an SVG icon, scaled to the marker size
+    /// (e.g. "icons/peak.svg")
+    Icon(&'a str),
+}
+
+/// The style of the markers drawn at point features
+#[derive(Clone)]
+pub struct MarkerStyle<'a> {
+    /// The shape of the markers
+    pub shape: MarkerShape<'a>,
+    /// The width and height of the markers in pixels
+    pub size: f64,
+    /// The attribute holding a value the marker size is proportional to, `None` for a fixed size
+    ///
+    /// The marker area grows with the value: the size is `size_scale` times its square root.
+    /// Features without a value get the fixed size.
+    pub size_field: Option<&'a str>,
+    /// The size in pixels per square root of the size field value
+    pub size_scale: f64,
+    /// The fill color of the markers
+    /// (e.g. "red", "#FF0000")
+    pub fill: &'a str,
+    /// The fill opacity of the markers (0.0 to 1.0)
+    pub fill_opacity: f64,
+    /// The outline color of the markers
+    /// (e.g. "white", "#FFFFFF")
+    pub stroke: &'a str,
+    /// The outline width of the markers in pixels
+    pub stroke_width: f64,
+}
+
+impl Default for MarkerStyle<'_> {
+    /// A 6 pixel black circle with a white outline
+    fn default() -> Self {
+        MarkerStyle {
+            shape: MarkerShape::Circle,
+            size: 6.0,
+            size_field: None,
+            size_scale: 1.0,
+            fill: "black",
+            fill_opacity: 1.0,
+            stroke: "white",
+            stroke_width: 1.0,
+        }
+    }
+}
+
+/// The style of the labels of a layer
+#[derive(Clone)]
+pub struct LabelStyle<'a> {
+    /// The attribute holding the text of a feature's label
+    /// (e.g. "name")
+    pub field: &'a str,
+    /// The preferred label languages in order, read from the field with the language
+    /// code as suffix before falling back to the field itself
+    /// (e.g. `&["de", "en"]` reads "`name_de`", then "`name_en`", then "name")
+    pub languages: &'a [&'a str],
+    /// The attribute holding the priority of a feature's label, lower values are placed first
+    /// (e.g. "scalerank"), `None` to place labels in the order they are read
+    pub priority_field: Option<&'a str>,
+    /// The font family, with fallbacks
+    /// (e.g. "Arial, Helvetica, sans-serif")
+    pub font_family: &'a str,
+    /// The font size in pixels, labels of a size that is not positive are left out
+    pub font_size: f64,
+    /// The font weight
+    /// (e.g. "normal", "bold", "600")
+    pub font_weight: &'a str,
+    /// Whether the text is set in italics
+    pub italic: bool,
+    /// Extra space between characters in pixels
+    pub letter_spacing: f64,
+    /// The text color, `None` to use the color of the feature: the fill color for points
+    /// and the stroke color for lines and polygons, or black if that color is "none"
+    /// (e.g. "black", "#000000")
+    pub fill: Option<&'a str>,
+    /// The color of the halo around the text, `None` for no halo
+    /// (e.g. "white", "#FFFFFF")
+    pub halo_color: Option<&'a str>,
+    /// The width of the halo around the text in pixels
+    pub halo_width: f64,
+    /// Which end of the text is placed at the anchor point plus offset
+    /// (e.g. "start", "middle", "end")
+    pub text_anchor: &'a str,
+    /// The preferred offset of the text from the anchor point in pixels, x to the right and y down
+    pub offset: (f64, f64),
+    /// Whether the text is converted to upper case
+    pub uppercase: bool,
+    /// The distance between repeated labels along line features in pixels,
+    /// `None` to leave line features unlabeled; a spacing that is not positive gives one label per line
+    pub line_spacing: Option<f64>,
+    /// Whether polygon features are labeled at their visual center
+    pub polygon_labels: bool,
+    /// The smallest on-screen area in square pixels of a polygon that gets a label
+    pub min_area: f64,
+    /// When set, polygon labels grow from the font size with the square root of
+    /// the on-screen area of the polygon, up to this size in pixels
+    pub max_font_size: Option<f64>,
+}
+
+impl Default for LabelStyle<'_> {
+    /// 12 pixel Arial labels centered on their anchor, prioritised by the Natural Earth `scalerank`
+    fn default() -> Self {
+        LabelStyle {
+            field: "name",
+            languages: &[],
+            priority_field: Some("scalerank"),
+            font_family: "Arial, Helvetica, sans-serif",
+            font_size: 12.0,
+            font_weight: "normal",
+            italic: false,
+            letter_spacing: 0.0,
+            fill: None,
+            halo_color: None,
+            halo_width: 0.0,
+            text_anchor: "middle",
+            offset: (0.0, 0.0),
+            uppercase: false,
+            line_spacing: None,
+            polygon_labels: false,
+            min_area: 1000.0,
+            max_font_size: None,
+        }
+    }
+}
+
+/// A condition on the attributes of a feature, drawing only the features that meet it
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FeatureFilter<'a> {
+    /// The field has one of the values, compared ignoring case, or as numbers for numeric fields
+    /// (e.g. field "featurecla", values `&["Lake", "Reservoir"]`)
+    OneOf {
+        /// The attribute to compare
+        field: &'a str,
+        /// The values of the features to draw
+        values: &'a [&'a str],
+    },
+    /// The numeric value of the field is within the bounds, both inclusive
+    /// (e.g. field "scalerank", min 0, max 4)
+    Between {
+        /// The attribute to compare
+        field: &'a str,
+        /// The lowest value of the features to draw
+        min: f64,
+        /// The highest value of the features to draw
+        max: f64,
+    },
+}
+
 /// The style of a layer, defining its visual appearance
+#[derive(Clone)]
 pub struct LayerStyle<'a> {
     /// The stroke (outline) color of the layer
     /// (e.g. "black", "#000000")
@@ -17,6 +322,505 @@ pub struct LayerStyle<'a> {
     /// The stroke width of the layer in pixels
     /// (e.g. "1" for 1 pixel width)
     pub stroke_width: &'a str,
+    /// The dash pattern of the stroke, `None` for a solid line
+    /// (e.g. "4 2" for 4 pixels on, 2 pixels off)
+    pub stroke_dasharray: Option<&'a str>,
+    /// The shape at the ends of open lines, `None` for the SVG default
+    /// (e.g. "butt", "round", "square")
+    pub stroke_linecap: Option<&'a str>,
+    /// The shape at the corners of lines, `None` for the default
+    /// (round for polylines, the SVG default for polygons)
+    /// (e.g. "miter", "round", "bevel")
+    pub stroke_linejoin: Option<&'a str>,
+    /// The limit on the ratio of miter length to stroke width, `None` for the SVG default
+    /// (e.g. "4")
+    pub stroke_miterlimit: Option<&'a str>,
+    /// Additional stroke passes drawn underneath the main stroke, in order
+    ///
+    /// A casing is a single wide pass beneath a narrower main stroke. Each pass is drawn for all
+    /// features of the layer before any main stroke, so casings of joining lines run unbroken.
+    pub stroke_passes: Vec<StrokePass<'a>>,
+    /// A pattern drawn on top of the fill color of polygons, `None` for a flat fill
+    pub fill_pattern: Option<FillPattern<'a>>,
+    /// A gradient replacing the fill color, `None` for a flat fill
+    pub fill_gradient: Option<Gradient<'a>>,
+    /// Water lines drawn around the polygons of the layer, `None` for no water lines
+    pub water_lines: Option<WaterLines<'a>>,
+    /// A filter effect applied to the layer as a whole, `None` for no filter
+    pub filter: Option<Filter<'a>>,
+    /// How the layer blends with the layers beneath it, `None` for normal blending
+    /// (e.g. "multiply", "screen", "overlay")
+    pub mix_blend_mode: Option<&'a str>,
+    /// The style of the labels of the features
+    pub label: LabelStyle<'a>,
+    /// The markers drawn at point features, `None` to only label them
+    pub marker: Option<MarkerStyle<'a>>,
+    /// The condition on the attributes of the features to draw, `None` to draw all features
+    ///
+    /// Features without the field do not meet the condition.
+    pub feature_filter: Option<FeatureFilter<'a>>,
+}
+
+impl Default for LayerStyle<'_> {
+    /// A black one pixel outline without fill
+    fn default() -> Self {
+        LayerStyle {
+            stroke: "black",
+            fill: "none",
+            fill_opacity: "1.0",
+            stroke_width: "1",
+            stroke_dasharray: None,
+            stroke_linecap: None,
+            stroke_linejoin: None,
+            stroke_miterlimit: None,
+            stroke_passes: Vec::new(),
+            fill_pattern: None,
+            fill_gradient: None,
+            water_lines: None,
+            filter: None,
+            mix_blend_mode: None,
+            label: LabelStyle::default(),
+            marker: None,
+            feature_filter: None,
+        }
+    }
+}
+
+/// The spacing, extent and labels of the graticule grid
+///
+/// Lines are drawn at multiples of the intervals, so a 30° interval gives lines at 0°, ±30°, ±60° and so on.
+pub struct Graticule<'a> {
+    /// Degrees of longitude between two major meridians
+    pub lon_interval: f64,
+    /// Degrees of latitude between two major parallels
+    pub lat_interval: f64,
+    /// Degrees of longitude between two minor meridians, `None` for no minor meridians
+    pub minor_lon_interval: Option<f64>,
+    /// Degrees of latitude between two minor parallels, `None` for no minor parallels
+    pub minor_lat_interval: Option<f64>,
+    /// The style of the minor lines, the major lines use the graticule style of the map style
+    pub minor_style: LayerStyle<'a>,
+    /// Whether to only draw lines within the map extent instead of around the whole globe
+    pub clip_to_extent: bool,
+    /// The font of the degree labels (e.g. "30°N") at the map edges, `None` for no labels
+    ///
+    /// Only the font, halo and fill are used, the fill defaults to the stroke of the major lines.
+    pub labels: Option<LabelStyle<'a>>,
+}
+
+impl Default for Graticule<'_> {
+    /// Unlabeled major lines every 15 degrees around the whole globe
+    fn default() -> Self {
+        Graticule {
+            lon_interval: 15.0,
+            lat_interval: 15.0,
+            minor_lon_interval: None,
+            minor_lat_interval: None,
+            minor_style: LayerStyle {
+                stroke_width: "0.25",
+                ..default_graticule_style()
+            },
+            clip_to_extent: false,
+            labels: None,
+        }
+    }
+}
+
+/// The frame around the map for print, drawn outside the map area
+///
+/// From the inside out, the frame consists of the neatline along the map edge,
+/// the coordinate ladder of alternating bars, tick marks and coordinate annotations.
+/// The ladder alternates at the minor graticule intervals if there are any, at the major ones otherwise.
+/// Ticks and annotations mark the major graticule lines.
+pub struct Frame<'a> {
+    /// Width in pixels of the margin the frame adds around the map on every side
+    pub margin: f64,
+    /// The color of the neatline and the ticks
+    pub color: &'a str,
+    /// Width of the neatline in pixels, the ladder outline and the ticks are half as wide
+    pub neatline_width: f64,
+    /// Width of the coordinate ladder in pixels, 0 for no ladder
+    pub ladder_width: f64,
+    /// The two alternating colors of the ladder bars
+    pub ladder_colors: (&'a str, &'a str),
+    /// Length of the tick marks in pixels, 0 for no ticks
+    pub tick_length: f64,
+    /// The font of the coordinate annotations, `None` for no annotations
+    ///
+    /// Only the font, halo and fill are used, the fill defaults to the frame color.
+    pub annotations: Option<LabelStyle<'a>>,
+}
+
+impl Default for Frame<'_> {
+    /// A black neatline with a black and white ladder, ticks and small annotations
+    fn default() -> Self {
+        Frame {
+            margin: 40.0,
+            color: "black",
+            neatline_width: 1.0,
+            ladder_width: 4.0,
+            ladder_colors: ("black", "white"),
+            tick_length: 4.0,
+            annotations: Some(LabelStyle {
+                font_size: 9.0,
+                ..LabelStyle::default()
+            }),
+        }
+    }
+}
+
+/// A corner of the map
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Corner {
+    /// The top left corner
+    TopLeft,
+    /// The top right corner
+    TopRight,
+    /// The bottom left corner
+    BottomLeft,
+    /// The bottom right corner
+    BottomRight,
+}
+
+/// A unit of ground distance
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DistanceUnit {
+    /// Kilometers
+    Kilometers,
+    /// Statute miles
+    Miles,
+    /// Nautical miles
+    NauticalMiles,
+}
+
+impl DistanceUnit {
+    /// Returns the length of the unit in meters
+    #[must_use]
+    pub fn meters(self) -> f64 {
+        match self {
+            DistanceUnit::Kilometers => 1000.0,
+            DistanceUnit::Miles => 1609.344,
+            DistanceUnit::NauticalMiles => 1852.0,
+        }
+    }
+
+    /// Returns the abbreviation of the unit, e.g. "km"
+    #[must_use]
+    pub fn symbol(self) -> &'static str {
+        match self {
+            DistanceUnit::Kilometers => "km",
+            DistanceUnit::Miles => "mi",
+            DistanceUnit::NauticalMiles => "nmi",
+        }
+    }
+}
+
+/// A scale bar showing a round ground distance, drawn in a corner of the map
+pub struct ScaleBar<'a> {
+    /// The corner of the map the scale bar is drawn in
+    pub corner: Corner,
+    /// The latitude at which the scale is measured, `None` for the center of the map
+    pub latitude: Option<f64>,
+    /// The unit of the distance
+    pub unit: DistanceUnit,
+    /// Maximum length of the bar in pixels, the bar is as long as the largest round distance that fits
+    pub max_length: f64,
+    /// Number of alternating segments of the bar
+    pub segments: u32,
+    /// Height of the bar in pixels
+    pub height: f64,
+    /// Distance of the scale bar from the map edges in pixels
+    pub padding: f64,
+    /// The two alternating colors of the segments
+    pub colors: (&'a str, &'a str),
+    /// The color of the outline of the bar
+    pub stroke: &'a str,
+    /// The font of the distance labels
+    ///
+    /// Only the font, halo and fill are used, the fill defaults to the stroke of the bar.
+    pub label: LabelStyle<'a>,
+}
+
+impl Default for ScaleBar<'_> {
+    /// A black and white bar of four segments of up to 150 pixels in kilometers at the bottom left
+    fn default() -> Self {
+        ScaleBar {
+            corner: Corner::BottomLeft,
+            latitude: None,
+            unit: DistanceUnit::Kilometers,
+            max_length: 150.0,
+            segments: 4,
+            height: 5.0,
+            padding: 15.0,
+            colors: ("black", "white"),
+            stroke: "black",
+            label: LabelStyle {
+                font_size: 10.0,
+                halo_color: Some("white"),
+                halo_width: 1.5,
+                ..LabelStyle::default()
+            },
+        }
+    }
+}
+
+/// Where a decoration is drawn on the map
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Placement {
+    /// In a corner of the map
+    Corner(Corner),
+    /// Centered at a pixel position of the map
+    Pixel(f64, f64),
+}
+
+/// The design of a north arrow
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NorthArrowKind {
+    /// A single arrow pointing north
+    Arrow,
+    /// A star of the four cardinal and four intercardinal directions
+    CompassRose,
+}
+
+/// An arrow or compass rose pointing to true north at its position on the map
+pub struct NorthArrow<'a> {
+    /// Where the arrow is drawn
+    pub placement: Placement,
+    /// The design of the arrow
+    pub kind: NorthArrowKind,
+    /// Length of the arrow or diameter of the compass rose in pixels
+    pub size: f64,
+    /// Distance of the arrow from the map edges in pixels, if placed in a corner
+    pub padding: f64,
+    /// The color of the dark halves of the arrow points
+    pub fill: &'a str,
+    /// The color of the light halves of the arrow points
+    pub light_fill: &'a str,
+    /// The color of the outline
+    pub stroke: &'a str,
+    /// The font of the direction letters, `None` for no letters
+    ///
+    /// Only the font, halo and fill are used, the fill defaults to the fill of the arrow.
+    pub label: Option<LabelStyle<'a>>,
+}
+
+impl Default for NorthArrow<'_> {
+    /// A black and white arrow with an "N" in the top right corner of the map
+    fn default() -> Self {
+        NorthArrow {
+            placement: Placement::Corner(Corner::TopRight),
+            kind: NorthArrowKind::Arrow,
+            size: 40.0,
+            padding: 15.0,
+            fill: "black",
+            light_fill: "white",
+            stroke: "black",
+            label: Some(LabelStyle {
+                font_size: 12.0,
+                font_weight: "bold",
+                halo_color: Some("white"),
+                halo_width: 1.5,
+                ..LabelStyle::default()
+            }),
+        }
+    }
+}
+
+/// Where the legend is drawn
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LegendPlacement {
+    /// Inside the map, in one of its corners
+    Inside(Corner),
+    /// Right of the map, in a margin added to the document
+    Beside,
+}
+
+/// A legend listing the layers of the map style that have a title
+///
+/// Each layer is shown with a sample of its style: its marker if it has one,
+/// a line if it has no fill, and an area otherwise.
+pub struct Legend<'a> {
+    /// Where the legend is drawn
+    pub placement: LegendPlacement,
+    /// The heading of the legend, `None` for no heading
+    pub title: Option<&'a str>,
+    /// The font of the entries, headings are bold and larger
+    ///
+    /// Only the font, halo and fill are used.
+    pub label: LabelStyle<'a>,
+    /// Width and height of the samples in pixels
+    pub swatch_size: (f64, f64),
+    /// Space in pixels between the border and the entries, and between the legend and the map edge
+    pub padding: f64,
+    /// The fill of the legend box, `None` for a transparent box
+    pub background: Option<&'a str>,
+    /// The color of the border of the legend box
+    pub stroke: &'a str,
+}
+
+impl Default for Legend<'_> {
+    /// A white box with a thin grey border in the bottom right corner of the map
+    fn default() -> Self {
+        Legend {
+            placement: LegendPlacement::Inside(Corner::BottomRight),
+            title: Some("Legend"),
+            label: LabelStyle {
+                font_size: 10.0,
+                text_anchor: "start",
+                ..LabelStyle::default()
+            },
+            swatch_size: (20.0, 12.0),
+            padding: 8.0,
+            background: Some("white"),
+            stroke: "#999999",
+        }
+    }
+}
+
+/// The title, subtitle, attribution and date of the map, drawn in bands above and below it
+///
+/// The title and subtitle go into a band above the map, the attribution and date into a band below it,
+/// the attribution at the left and the date at the right. Each band is only added if it has any text.
+pub struct TitleBlock<'a> {
+    /// The title of the map, `None` for no title
+    pub title: Option<&'a str>,
+    /// The subtitle below the title, `None` for no subtitle
+    pub subtitle: Option<&'a str>,
+    /// The source of the data, `None` for no attribution
+    pub attribution: Option<&'a str>,
+    /// The date stamp, e.g. from [`super::utils::today`], `None` for no date
+    pub date: Option<&'a str>,
+    /// The font of the title, its anchor aligns the title and subtitle with the map
+    ///
+    /// Only the font, halo, fill and anchor are used, the fill defaults to black.
+    pub title_style: LabelStyle<'a>,
+    /// The font of the subtitle
+    pub subtitle_style: LabelStyle<'a>,
+    /// The font of the attribution and date
+    pub note_style: LabelStyle<'a>,
+    /// Space in pixels above and below the text of each band
+    pub padding: f64,
+}
+
+impl Default for TitleBlock<'_> {
+    /// A Natural Earth attribution in small grey print, without title or date
+    fn default() -> Self {
+        TitleBlock {
+            title: None,
+            subtitle: None,
+            attribution: Some("Made with Natural Earth"),
+            date: None,
+            title_style: LabelStyle {
+                font_size: 24.0,
+                font_weight: "bold",
+                ..LabelStyle::default()
+            },
+            subtitle_style: LabelStyle {
+                font_size: 14.0,
+                ..LabelStyle::default()
+            },
+            note_style: LabelStyle {
+                font_size: 9.0,
+                fill: Some("#555555"),
+                ..LabelStyle::default()
+            },
+            padding: 10.0,
+        }
+    }
+}
+
+/// The value GPX tracks are colored by
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TrackColoring {
+    /// The elevation of the track points
+    Elevation,
+    /// The time the track points were recorded
+    Time,
+}
+
+/// The style of the tracks, routes and waypoints of a GPX file
+pub struct GpxStyle<'a> {
+    /// The style of tracks and routes
+    pub track_style: LayerStyle<'a>,
+    /// The style of waypoints, labeled by their "name"
+    pub waypoint_style: LayerStyle<'a>,
+    /// The value the tracks are colored by, `None` to draw them in the stroke color of their style
+    pub coloring: Option<TrackColoring>,
+    /// The colors of equal intervals of the coloring value, from the lowest to the highest
+    ///
+    /// Track points without the value take the color of the interval of the point before them.
+    pub colors: &'a [&'a str],
+}
+
+impl Default for GpxStyle<'_> {
+    /// Red two pixel tracks and red waypoints with haloed labels above them,
+    /// colored from blue to red when a coloring is set
+    fn default() -> Self {
+        GpxStyle {
+            track_style: LayerStyle {
+                stroke: "#D7191C",
+                stroke_width: "2",
+                stroke_linecap: Some("round"),
+                stroke_linejoin: Some("round"),
+                ..LayerStyle::default()
+            },
+            waypoint_style: LayerStyle {
+                marker: Some(MarkerStyle {
+                    fill: "#D7191C",
+                    ..MarkerStyle::default()
+                }),
+                label: LabelStyle {
+                    priority_field: None,
+                    font_size: 10.0,
+                    fill: Some("black"),
+                    halo_color: Some("white"),
+                    halo_width: 2.0,
+                    offset: (0.0, -8.0),
+                    ..LabelStyle::default()
+                },
+                ..LayerStyle::default()
+            },
+            coloring: None,
+            colors: &["#2C7BB6", "#ABD9E9", "#FFFFBF", "#FDAE61", "#D7191C"],
+        }
+    }
+}
+
+/// A single meridian or parallel drawn with a style of its own
+pub enum ReferenceLine<'a> {
+    /// A parallel at a latitude in degrees
+    Parallel {
+        /// The latitude of the line
+        lat: f64,
+        /// The style of the line
+        layer_style: LayerStyle<'a>,
+    },
+    /// A meridian at a longitude in degrees
+    Meridian {
+        /// The longitude of the line
+        lon: f64,
+        /// The style of the line
+        layer_style: LayerStyle<'a>,
+    },
+}
+
+/// The special parallels and meridians of the map, each drawn only if it has a style
+#[derive(Default)]
+pub struct ReferenceLines<'a> {
+    /// The style of the Tropic of Cancer
+    pub tropic_of_cancer: Option<LayerStyle<'a>>,
+    /// The style of the Tropic of Capricorn
+    pub tropic_of_capricorn: Option<LayerStyle<'a>>,
+    /// The style of the Arctic Circle
+    pub arctic_circle: Option<LayerStyle<'a>>,
+    /// The style of the Antarctic Circle
+    pub antarctic_circle: Option<LayerStyle<'a>>,
+    /// The style of the prime meridian
+    pub prime_meridian: Option<LayerStyle<'a>>,
+    /// The style of the antimeridian
+    pub antimeridian: Option<LayerStyle<'a>>,
+    /// Any further lines, e.g. a parallel marking a border
+    pub custom: Vec<ReferenceLine<'a>>,
 }
 
 /// A layer in the map, combining a style with its source file
@@ -25,7 +829,20 @@ pub struct Layer<'a> {
     pub layer_style: LayerStyle<'a>,
     /// The filename of the shapefile containing this layer's data
     /// (e.g. "`ne_10m_land.shp`")
+    ///
+    /// Relative paths are looked up in `data/10m_physical`, so own data can be given by an absolute path.
+    /// Files ending in `.geojson` or `.json` are read as `GeoJSON` instead,
+    /// with the properties of the features as their attributes.
+    /// Files ending in `.zip` are read as the shapefile in the archive, without extracting it.
+    /// A table of a `GeoPackage` is given after a `#`, e.g. `natural_earth_vector.gpkg#ne_10m_land`,
+    /// as is the shapefile of an archive holding several.
     pub filename: &'a str,
+    /// The title of the layer in the legend, `None` to leave the layer out of the legend
+    ///
+    /// Layers with the same title are listed together under it, each with its description.
+    pub title: Option<&'a str>,
+    /// A description of the layer in the legend, e.g. the depth range of a bathymetry layer
+    pub description: Option<&'a str>,
 }
 
 /// A complete map style, defining the background and all layers
@@ -36,8 +853,12 @@ pub struct Style<'a> {
     pub layers: Vec<Layer<'a>>,
     /// The style for the graticule grid
     pub graticule_style: LayerStyle<'a>,
+    /// The spacing, extent and labels of the graticule grid
+    pub graticule: Graticule<'a>,
     /// The style for the equator line
     pub equator_style: LayerStyle<'a>,
+    /// The tropics, polar circles and other special lines
+    pub reference_lines: ReferenceLines<'a>,
 }
 
 /// Returns the default graticule style
@@ -53,6 +874,7 @@ pub fn default_graticule_style<'a>() -> LayerStyle<'a> {
         fill_opacity: "0.0",
         stroke: "#999999",
         stroke_width: "0.5",
+        ..LayerStyle::default()
     }
 }
 
@@ -69,6 +891,22 @@ pub fn default_equator_style<'a>() -> LayerStyle<'a> {
         fill_opacity: "0.0",
         stroke: "#FF0000",
         stroke_width: "1.0",
+        ..LayerStyle::default()
+    }
+}
+
+/// Returns the default style of the tropics and polar circles
+///
+/// This style uses:
+/// - Dashed red (#FF0000) lines
+/// - 0.75 pixel width
+/// - No fill
+#[must_use]
+pub fn default_reference_line_style<'a>() -> LayerStyle<'a> {
+    LayerStyle {
+        stroke_width: "0.75",
+        stroke_dasharray: Some("4 2"),
+        ..default_equator_style()
     }
 }
 
@@ -91,11 +929,16 @@ pub fn classic_style<'a>() -> Style<'a> {
                 fill: "lightseagreen",
                 fill_opacity: "0.5",
                 stroke_width: "1",
+                ..LayerStyle::default()
             },
             filename: "ne_10m_ocean.shp",
+            title: Some("Ocean"),
+            description: None,
         },
         graticule_style: default_graticule_style(),
+        graticule: Graticule::default(),
         equator_style: default_equator_style(),
+        reference_lines: ReferenceLines::default(),
         layers: vec![
             Layer {
                 layer_style: LayerStyle {
@@ -103,8 +946,11 @@ pub fn classic_style<'a>() -> Style<'a> {
                     fill: "wheat",
                     fill_opacity: "1.0",
                     stroke_width: "0",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_land.shp",
+                title: Some("Land"),
+                description: None,
             },
             Layer {
                 layer_style: LayerStyle {
@@ -112,8 +958,11 @@ pub fn classic_style<'a>() -> Style<'a> {
                     fill: "skyblue",
                     fill_opacity: "1.0",
                     stroke_width: "0",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_lakes.shp",
+                title: Some("Lakes"),
+                description: None,
             },
             Layer {
                 layer_style: LayerStyle {
@@ -121,8 +970,11 @@ pub fn classic_style<'a>() -> Style<'a> {
                     fill: "silver",
                     fill_opacity: "1.0",
                     stroke_width: "0",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_reefs.shp",
+                title: Some("Reefs"),
+                description: None,
             },
             Layer {
                 layer_style: LayerStyle {
@@ -130,8 +982,11 @@ pub fn classic_style<'a>() -> Style<'a> {
                     fill: "lightcyan",
                     fill_opacity: "1.0",
                     stroke_width: "0",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_antarctic_ice_shelves_polys.shp",
+                title: Some("Ice shelves"),
+                description: None,
             },
             Layer {
                 layer_style: LayerStyle {
@@ -139,8 +994,11 @@ pub fn classic_style<'a>() -> Style<'a> {
                     fill: "aliceblue",
                     fill_opacity: "1.0",
                     stroke_width: "0",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_glaciated_areas.shp",
+                title: Some("Glaciers"),
+                description: None,
             },
             Layer {
                 layer_style: LayerStyle {
@@ -148,8 +1006,11 @@ pub fn classic_style<'a>() -> Style<'a> {
                     fill: "none",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_rivers_lake_centerlines.shp",
+                title: Some("Rivers"),
+                description: None,
             },
         ],
     }
@@ -162,6 +1023,7 @@ pub fn classic_style<'a>() -> Style<'a> {
 /// - Darkest blue (#023858) for deep water (10000m)
 /// - Additional layers for land features in muted colors
 #[must_use]
+#[allow(clippy::too_many_lines)]
 pub fn ocean_style<'a>() -> Style<'a> {
     Style {
         background: Layer {
@@ -170,11 +1032,16 @@ pub fn ocean_style<'a>() -> Style<'a> {
                 fill: "#023858",
                 fill_opacity: "1.0",
                 stroke_width: "1",
+                ..LayerStyle::default()
             },
             filename: "ne_10m_bathymetry_A_10000.shp",
+            title: Some("Ocean depth"),
+            description: Some("deeper than 10000 m"),
         },
         graticule_style: default_graticule_style(),
+        graticule: Graticule::default(),
         equator_style: default_equator_style(),
+        reference_lines: ReferenceLines::default(),
         layers: vec![
             Layer {
                 layer_style: LayerStyle {
@@ -182,8 +1049,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "#fff7fb",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_bathymetry_L_0.shp",
+                title: Some("Ocean depth"),
+                description: Some("0–200 m"),
             },
             Layer {
                 layer_style: LayerStyle {
@@ -191,8 +1061,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "#ece7f2",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_bathymetry_K_200.shp",
+                title: Some("Ocean depth"),
+                description: Some("200–1000 m"),
             },
             Layer {
                 layer_style: LayerStyle {
@@ -200,8 +1073,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "#d0d1e6",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_bathymetry_J_1000.shp",
+                title: Some("Ocean depth"),
+                description: Some("1000–2000 m"),
             },
             Layer {
                 layer_style: LayerStyle {
@@ -209,8 +1085,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "#a6bddb",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_bathymetry_I_2000.shp",
+                title: Some("Ocean depth"),
+                description: Some("2000–3000 m"),
             },
             Layer {
                 layer_style: LayerStyle {
@@ -218,8 +1097,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "#74a9cf",
                     fill_opacity: "1.0",
                     stroke_width: "0",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_bathymetry_H_3000.shp",
+                title: Some("Ocean depth"),
+                description: Some("3000–4000 m"),
             },
             Layer {
                 layer_style: LayerStyle {
@@ -227,8 +1109,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "#3690c0",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_bathymetry_G_4000.shp",
+                title: Some("Ocean depth"),
+                description: Some("4000–5000 m"),
             },
             Layer {
                 layer_style: LayerStyle {
@@ -236,8 +1121,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "#0570b0",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_bathymetry_F_5000.shp",
+                title: Some("Ocean depth"),
+                description: Some("5000–6000 m"),
             },
             Layer {
                 layer_style: LayerStyle {
@@ -245,8 +1133,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "#045a8d",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_bathymetry_E_6000.shp",
+                title: Some("Ocean depth"),
+                description: Some("6000–7000 m"),
             },
             Layer {
                 layer_style: LayerStyle {
@@ -254,8 +1145,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "#023858",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_bathymetry_D_7000.shp",
+                title: Some("Ocean depth"),
+                description: Some("7000–8000 m"),
             },
             Layer {
                 layer_style: LayerStyle {
@@ -263,8 +1157,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "#023858",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_bathymetry_C_8000.shp",
+                title: Some("Ocean depth"),
+                description: Some("8000–9000 m"),
             },
             Layer {
                 layer_style: LayerStyle {
@@ -272,8 +1169,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "#023858",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_bathymetry_B_9000.shp",
+                title: Some("Ocean depth"),
+                description: Some("9000–10000 m"),
             },
             Layer {
                 layer_style: LayerStyle {
@@ -281,8 +1181,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "dimgray",
                     fill_opacity: "1.0",
                     stroke_width: "0",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_land.shp",
+                title: Some("Land"),
+                description: None,
             },
             Layer {
                 layer_style: LayerStyle {
@@ -290,8 +1193,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "skyblue",
                     fill_opacity: "1.0",
                     stroke_width: "0",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_lakes.shp",
+                title: Some("Lakes"),
+                description: None,
             },
             Layer {
                 layer_style: LayerStyle {
@@ -299,8 +1205,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "silver",
                     fill_opacity: "1.0",
                     stroke_width: "0",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_reefs.shp",
+                title: Some("Reefs"),
+                description: None,
             },
             Layer {
                 layer_style: LayerStyle {
@@ -308,8 +1217,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "lightcyan",
                     fill_opacity: "1.0",
                     stroke_width: "0",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_antarctic_ice_shelves_polys.shp",
+                title: Some("Ice shelves"),
+                description: None,
             },
             Layer {
                 layer_style: LayerStyle {
@@ -317,8 +1229,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "aliceblue",
                     fill_opacity: "1.0",
                     stroke_width: "0",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_glaciated_areas.shp",
+                title: Some("Glaciers"),
+                description: None,
             },
             Layer {
                 layer_style: LayerStyle {
@@ -326,8 +1241,11 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "none",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_rivers_lake_centerlines.shp",
+                title: Some("Rivers"),
+                description: None,
             },
             Layer {
                 layer_style: LayerStyle {
@@ -335,18 +1253,23 @@ pub fn ocean_style<'a>() -> Style<'a> {
                     fill: "black",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_geography_regions_points.shp",
+                title: None,
+                description: None,
             },
-
             Layer {
                 layer_style: LayerStyle {
                     stroke: "black",
                     fill: "black",
                     fill_opacity: "1.0",
                     stroke_width: "1",
+                    ..LayerStyle::default()
                 },
                 filename: "ne_10m_geography_regions_elevation_points.shp",
+                title: Some("Elevation points"),
+                description: None,
             },
         ],
     }
@@ -366,19 +1289,27 @@ pub fn grey_style<'a>() -> Style<'a> {
                 fill: "silver",
                 fill_opacity: "0",
                 stroke_width: "0",
+                ..LayerStyle::default()
             },
             filename: "ne_10m_ocean.shp",
+            title: Some("Ocean"),
+            description: None,
         },
         graticule_style: default_graticule_style(),
+        graticule: Graticule::default(),
         equator_style: default_equator_style(),
+        reference_lines: ReferenceLines::default(),
         layers: vec![Layer {
             layer_style: LayerStyle {
                 stroke: "white",
                 fill: "black",
                 fill_opacity: "1.0",
                 stroke_width: "1",
+                ..LayerStyle::default()
             },
             filename: "ne_10m_land.shp",
+            title: Some("Land"),
+            description: None,
         }],
     }
 }
@@ -397,19 +1328,27 @@ pub fn grey_style_transparent<'a>() -> Style<'a> {
                 fill: "silver",
                 fill_opacity: "0",
                 stroke_width: "0",
+                ..LayerStyle::default()
             },
             filename: "ne_10m_ocean.shp",
+            title: Some("Ocean"),
+            description: None,
         },
         graticule_style: default_graticule_style(),
+        graticule: Graticule::default(),
         equator_style: default_equator_style(),
+        reference_lines: ReferenceLines::default(),
         layers: vec![Layer {
             layer_style: LayerStyle {
                 stroke: "white",
                 fill: "black",
                 fill_opacity: "1.0",
                 stroke_width: "1",
+                ..LayerStyle::default()
             },
             filename: "ne_10m_land.shp",
+            title: Some("Land"),
+            description: None,
         }],
     }
 }
@@ -428,19 +1367,27 @@ pub fn grey_style_110<'a>() -> Style<'a> {
                 fill: "silver",
                 fill_opacity: "0.5",
                 stroke_width: "1",
+                ..LayerStyle::default()
             },
             filename: "ne_110m_ocean.shp",
+            title: Some("Ocean"),
+            description: None,
         },
         graticule_style: default_graticule_style(),
+        graticule: Graticule::default(),
         equator_style: default_equator_style(),
+        reference_lines: ReferenceLines::default(),
         layers: vec![Layer {
             layer_style: LayerStyle {
                 stroke: "none",
                 fill: "dimgray",
                 fill_opacity: "1.0",
                 stroke_width: "0",
+                ..LayerStyle::default()
             },
             filename: "ne_110m_land.shp",
+            title: Some("Land"),
+            description: None,
         }],
     }
 }
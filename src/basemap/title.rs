@@ -0,0 +1,105 @@
+//! This module provides functionality for drawing the title block in bands above and below the map.
+
+use svg::Node;
+
+use super::Map;
+use super::draw_svg::draw_label;
+use super::styles::{LabelStyle, TitleBlock};
+
+/// Height of the capital letters above the baseline relative to the font size
+const CAP_HEIGHT: f64 = 0.8;
+/// Vertical space in pixels between the title and the subtitle
+const LINE_GAP: f64 = 4.0;
+
+/// Returns the heights in pixels of the bands above and below the map, zero for a band without text
+#[must_use]
+pub fn title_bands(title_block: &TitleBlock) -> (f64, f64) {
+    let font_sizes: Vec<f64> = top_lines(title_block)
+        .iter()
+        .map(|(_, label_style)| label_style.font_size)
+        .collect();
+    let top = if font_sizes.is_empty() {
+        0.0
+    } else {
+        let lines: f64 = font_sizes
+            .iter()
+            .map(|font_size| font_size + LINE_GAP)
+            .sum();
+        2.0 * title_block.padding + lines - LINE_GAP
+    };
+    let bottom = if title_block.attribution.is_none() && title_block.date.is_none() {
+        0.0
+    } else {
+        2.0 * title_block.padding + title_block.note_style.font_size
+    };
+    (top, bottom)
+}
+
+/// Draws the title and subtitle above the map and the attribution and date below it
+///
+/// The title and subtitle are aligned with the map by the anchor of their style.
+///
+/// # Arguments
+/// * `map` - The map dimensions and projection settings
+/// * `document` - The SVG document or group to modify
+/// * `title_block` - The texts and their fonts
+/// * `bands` - The y coordinates of the tops of the bands above and below the map
+pub fn draw_title_block(
+    map: &Map,
+    document: &mut impl Node,
+    title_block: &TitleBlock,
+    bands: (f64, f64),
+) {
+    let width = f64::from(map.cols);
+
+    let mut y = bands.0 + title_block.padding;
+    for (text, label_style) in top_lines(title_block) {
+        let baseline = y + CAP_HEIGHT * label_style.font_size;
+        let x = anchor_x(label_style.text_anchor, width);
+        let fill = label_style.fill.unwrap_or("black");
+        draw_label((x, baseline), text, document, &label_style, fill);
+        y += label_style.font_size + LINE_GAP;
+    }
+
+    let note_style = &title_block.note_style;
+    let fill = note_style.fill.unwrap_or("black");
+    let baseline = bands.1 + title_block.padding + CAP_HEIGHT * note_style.font_size;
+    if let Some(attribution) = title_block.attribution {
+        let left = LabelStyle {
+            text_anchor: "start",
+            ..note_style.clone()
+        };
+        draw_label((0.0, baseline), attribution, document, &left, fill);
+    }
+    if let Some(date) = title_block.date {
+        let right = LabelStyle {
+            text_anchor: "end",
+            ..note_style.clone()
+        };
+        draw_label((width, baseline), date, document, &right, fill);
+    }
+}
+
+/// Returns the texts of the band above the map with their styles, the subtitle using the anchor of the title
+fn top_lines<'t>(title_block: &'t TitleBlock) -> Vec<(&'t str, LabelStyle<'t>)> {
+    let subtitle_style = LabelStyle {
+        text_anchor: title_block.title_style.text_anchor,
+        ..title_block.subtitle_style.clone()
+    };
+    [
+        (title_block.title, title_block.title_style.clone()),
+        (title_block.subtitle, subtitle_style),
+    ]
+    .into_iter()
+    .filter_map(|(text, label_style)| text.map(|text| (text, label_style)))
+    .collect()
+}
+
+/// Returns the x coordinate of a text anchor aligning a text with the map
+fn anchor_x(text_anchor: &str, width: f64) -> f64 {
+    match text_anchor {
+        "start" => 0.0,
+        "end" => width,
+        _ => width / 2.0,
+    }
+}
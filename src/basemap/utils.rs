@@ -1,6 +1,7 @@
 //! Module for basemap utils
 
 use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use image::ImageBuffer;
 use resvg::usvg;
@@ -30,7 +31,74 @@ pub fn mapping_function(lon: f64, lat: f64, map: &Map) -> (f64, f64) {
     mapping_fn2(mapping_fn1((lon, lat)))
 }
 
+/// Map from a pixel position back to lon,lat
+///
+/// The position is found numerically with Newton's method on [`mapping_function`],
+/// so this holds for whatever projection the mapping uses.
+/// Returns `None` if the iteration does not converge, e.g. outside the projected globe.
+#[must_use]
+pub fn inverse_mapping_function(x: f64, y: f64, map: &Map) -> Option<(f64, f64)> {
+    const STEP: f64 = 1e-6;
+    const MAX_ITERATIONS: u32 = 50;
+    const TOLERANCE: f64 = 1e-6;
+
+    let mut lon = f64::midpoint(map.lon_min, map.lon_max);
+    let mut lat = f64::midpoint(map.lat_min, map.lat_max);
+    for _ in 0..MAX_ITERATIONS {
+        let (px, py) = mapping_function(lon, lat, map);
+        let (fx, fy) = (px - x, py - y);
+        if fx.hypot(fy) < TOLERANCE {
+            return Some((lon, lat));
+        }
+
+        // Jacobian of the mapping by forward differences
+        let (lon_x, lon_y) = mapping_function(lon + STEP, lat, map);
+        let (lat_x, lat_y) = mapping_function(lon, lat + STEP, map);
+        let (x_lon, y_lon) = ((lon_x - px) / STEP, (lon_y - py) / STEP);
+        let (x_lat, y_lat) = ((lat_x - px) / STEP, (lat_y - py) / STEP);
+        let determinant = x_lon * y_lat - x_lat * y_lon;
+        if !determinant.is_finite() || determinant.abs() < f64::EPSILON {
+            return None;
+        }
+        lon -= (y_lat * fx - x_lat * fy) / determinant;
+        lat -= (x_lon * fy - y_lon * fx) / determinant;
+        if !lon.is_finite() || !lat.is_finite() {
+            return None;
+        }
+    }
+    None
+}
+
+/// Returns today's date in UTC as year-month-day, e.g. "2024-05-31"
+#[must_use]
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let days = i64::try_from(seconds / 86_400).unwrap_or(0);
+
+    // Civil date from days since 1970-01-01, counting eras of 400 years from March 2000
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
 /// Convert from svg to png
+///
+/// # Panics
+/// Panics if the svg cannot be read or parsed, or the png cannot be written.
 pub fn svg_to_png(input_svg_path: &PathBuf, output_png_path: &PathBuf) {
     let tree = create_svg_tree(input_svg_path);
     let pixmap_size = tree.size().to_int_size();
@@ -43,6 +111,9 @@ pub fn svg_to_png(input_svg_path: &PathBuf, output_png_path: &PathBuf) {
 }
 
 /// Convert from svg to buffer
+///
+/// # Panics
+/// Panics if the svg cannot be read or parsed.
 #[must_use]
 pub fn svg_to_image_buffer(input_svg_path: &PathBuf) -> ImageBuffer<image::Rgba<u8>, Vec<u8>> {
     let tree = create_svg_tree(input_svg_path);
@@ -59,18 +130,15 @@ pub fn svg_to_image_buffer(input_svg_path: &PathBuf) -> ImageBuffer<image::Rgba<
 
 /// Create a svg tree from a file
 fn create_svg_tree(input_svg_path: &PathBuf) -> usvg::Tree {
-    let tree = {
-        let mut opt = usvg::Options::<'_> {
-            resources_dir: std::fs::canonicalize(input_svg_path)
-                .ok()
-                .and_then(|p| p.parent().map(std::path::Path::to_path_buf)),
-            ..Default::default()
-        };
-
-        opt.fontdb_mut().load_system_fonts();
-
-        let svg_data = std::fs::read(input_svg_path).expect("Error reading svg");
-        usvg::Tree::from_data(&svg_data, &opt).expect("Error parsing svg")
+    let mut opt = usvg::Options::<'_> {
+        resources_dir: std::fs::canonicalize(input_svg_path)
+            .ok()
+            .and_then(|p| p.parent().map(std::path::Path::to_path_buf)),
+        ..Default::default()
     };
-    tree
+
+    opt.fontdb_mut().load_system_fonts();
+
+    let svg_data = std::fs::read(input_svg_path).expect("Error reading svg");
+    usvg::Tree::from_data(&svg_data, &opt).expect("Error parsing svg")
 }
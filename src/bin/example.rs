@@ -1,5 +1,8 @@
 use clap::Parser;
-use natural_earth_basemap::basemap::{ draw_map, styles,Map};
+use natural_earth_basemap::basemap::{
+    Map, csv_points::CsvColumns, draw_map_with_layout_reporting, gpx, layout::Layout,
+    overlay::Overlay, styles, utils,
+};
 
 /// Visualize Example
 #[derive(Parser, Debug)]
@@ -32,6 +35,42 @@ struct Args {
     /// Output file path
     #[arg(long, default_value = "Map.svg")]
     output_path: std::path::PathBuf,
+
+    /// Preferred label languages in order, falling back to the default names (e.g. "de,fr")
+    #[arg(long, value_delimiter = ',')]
+    languages: Vec<String>,
+
+    /// Draw a print frame with coordinate ladder and annotations around the map
+    #[arg(long)]
+    frame: bool,
+
+    /// Draw a scale bar in the bottom left corner of the map
+    #[arg(long)]
+    scale_bar: bool,
+
+    /// Draw a legend of the layers beside the map
+    #[arg(long)]
+    legend: bool,
+
+    /// Draw a north arrow in the top right corner of the map
+    #[arg(long)]
+    north_arrow: bool,
+
+    /// Title of the map, drawn above it with the attribution and date below
+    #[arg(long)]
+    title: Option<String>,
+
+    /// GPX file whose tracks and waypoints are drawn on top of the map
+    #[arg(long)]
+    gpx: Option<std::path::PathBuf>,
+
+    /// Color the GPX tracks by elevation
+    #[arg(long)]
+    gpx_elevation: bool,
+
+    /// CSV file of points with "latitude", "longitude" and "name" columns, drawn on top of the map
+    #[arg(long)]
+    csv: Option<std::path::PathBuf>,
 }
 
 pub fn main() {
@@ -48,7 +87,94 @@ pub fn main() {
 
     let output_path = args.output_path;
 
-    draw_map(&map,&styles::ocean_style(), &output_path);
-}
+    let languages: Vec<&str> = args.languages.iter().map(String::as_str).collect();
+    let mut style = styles::ocean_style();
+    // Label lakes inside their areas and rivers along their lines, mark elevation points with triangles
+    for layer in &mut style.layers {
+        match layer.filename {
+            "ne_10m_lakes.shp" => {
+                layer.layer_style.label = styles::LabelStyle {
+                    italic: true,
+                    fill: Some("steelblue"),
+                    polygon_labels: true,
+                    max_font_size: Some(24.0),
+                    ..styles::LabelStyle::default()
+                };
+            }
+            "ne_10m_rivers_lake_centerlines.shp" => {
+                layer.layer_style.label = styles::LabelStyle {
+                    italic: true,
+                    line_spacing: Some(400.0),
+                    ..styles::LabelStyle::default()
+                };
+            }
+            "ne_10m_geography_regions_elevation_points.shp" => {
+                layer.layer_style.marker = Some(styles::MarkerStyle {
+                    shape: styles::MarkerShape::Triangle,
+                    ..styles::MarkerStyle::default()
+                });
+            }
+            _ => {}
+        }
+        layer.layer_style.label.languages = &languages;
+    }
+    // Only draw the graticule lines within the map extent
+    style.graticule.clip_to_extent = true;
 
+    let gpx_style = styles::GpxStyle {
+        coloring: args
+            .gpx_elevation
+            .then_some(styles::TrackColoring::Elevation),
+        ..styles::GpxStyle::default()
+    };
+    let mut overlays = args
+        .gpx
+        .as_deref()
+        .map(|path| gpx::gpx_overlays(path, &gpx_style))
+        .unwrap_or_default();
+    if let Some(path) = args.csv.as_deref() {
+        let layer_style = styles::LayerStyle {
+            marker: Some(styles::MarkerStyle::default()),
+            label: styles::LabelStyle {
+                fill: Some("black"),
+                offset: (0.0, -8.0),
+                ..styles::LabelStyle::default()
+            },
+            ..styles::LayerStyle::default()
+        };
+        let overlay = Overlay::from_csv(path, &CsvColumns::default(), layer_style);
+        if overlay.skipped > 0 {
+            eprintln!(
+                "Skipped {} rows without a valid position in {}",
+                overlay.skipped,
+                path.display()
+            );
+        }
+        overlays.push(overlay);
+    }
 
+    let today = utils::today();
+    let layout = Layout {
+        overlays,
+        frame: args.frame.then(styles::Frame::default),
+        scale_bar: args.scale_bar.then(styles::ScaleBar::default),
+        legend: args.legend.then(|| styles::Legend {
+            placement: styles::LegendPlacement::Beside,
+            ..styles::Legend::default()
+        }),
+        north_arrow: args.north_arrow.then(styles::NorthArrow::default),
+        title_block: args.title.as_deref().map(|title| styles::TitleBlock {
+            title: Some(title),
+            date: Some(&today),
+            ..styles::TitleBlock::default()
+        }),
+        ..Layout::default()
+    };
+
+    for skipped in draw_map_with_layout_reporting(&map, &style, &layout, &output_path) {
+        eprintln!(
+            "Skipped {} features without drawable geometry in {}",
+            skipped.count, skipped.filename
+        );
+    }
+}